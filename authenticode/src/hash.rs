@@ -0,0 +1,266 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem::size_of;
+use der::asn1::ObjectIdentifier;
+use digest::Digest;
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use winpe::{image, DirectoryEntry, File, PeHeaders};
+use {spc, invalid_data};
+
+// The file offsets the Authenticode hash is defined in terms of
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageLayout {
+    pub check_sum: u64,
+    pub security_directory: u64,
+    pub size_of_headers: u64,
+    // (PointerToRawData, SizeOfRawData) sorted by file offset, empty sections left out
+    pub sections: Vec<(u64, u64)>,
+    pub certificate_table: Option<(u64, u64)>,
+    pub len: u64,
+}
+
+impl ImageLayout {
+    pub fn new<R: Read + Seek>(mut read: R) -> io::Result<Self> {
+        try!(read.seek(SeekFrom::Start(0)));
+        let (check_sum, security_directory, size_of_headers, mut sections, certificate_table) = {
+            let file = try!(File::new(&mut read));
+            let nt = file.nt_headers();
+            let optional_header = file.dos_header().lfanew() as u64 + size_of::<u32>() as u64 + size_of::<image::FileHeader>() as u64;
+
+            if file.directory_headers().len() <= image::DIRECTORY_ENTRY_SECURITY {
+                return Err(invalid_data("security directory entry not present"))
+            }
+
+            let sections = file.section_headers().iter()
+                .filter(|section| section.size_of_raw_data() != 0)
+                .map(|section| (section.pointer_to_raw_data() as u64, section.size_of_raw_data() as u64))
+                .collect::<Vec<_>>();
+
+            (
+                // CheckSum sits at the same offset in PE32 and PE32+ optional headers
+                optional_header + 64,
+                optional_header + file.kind().size_of_optional_header() as u64 + (image::DIRECTORY_ENTRY_SECURITY * size_of::<image::DataDirectory>()) as u64,
+                nt.size_of_headers() as u64,
+                sections,
+                file.directory_header(DirectoryEntry::Security).map(|data| (data.virtual_address() as u64, data.size() as u64)),
+            )
+        };
+        sections.sort();
+
+        // the hash skips the security directory entry, which has to lie within the headers
+        if security_directory + size_of::<image::DataDirectory>() as u64 > size_of_headers {
+            return Err(invalid_data("bad SizeOfHeaders"))
+        }
+
+        let len = try!(read.seek(SeekFrom::End(0)));
+        if size_of_headers > len || sections.iter().any(|&(offset, size)| offset + size > len) {
+            return Err(invalid_data("image truncated"))
+        }
+        if let Some((offset, size)) = certificate_table {
+            if offset + size > len {
+                return Err(invalid_data("certificate table past EOF"))
+            }
+        }
+
+        Ok(ImageLayout {
+            check_sum: check_sum,
+            security_directory: security_directory,
+            size_of_headers: size_of_headers,
+            sections: sections,
+            certificate_table: certificate_table,
+            len: len,
+        })
+    }
+
+    // (offset, size) ranges of the file that are hashed, in hashing order
+    pub fn ranges(&self) -> Vec<(u64, u64)> {
+        let dir_size = size_of::<image::DataDirectory>() as u64;
+        let mut ranges = vec![
+            (0, self.check_sum),
+            (self.check_sum + size_of::<u32>() as u64, self.security_directory - self.check_sum - size_of::<u32>() as u64),
+            (self.security_directory + dir_size, self.size_of_headers - self.security_directory - dir_size),
+        ];
+        ranges.extend(self.sections.iter().cloned());
+
+        // anything past the last section is hashed too, except the certificate table itself
+        let end = self.sections.iter().map(|&(offset, size)| offset + size).max().unwrap_or(0).max(self.size_of_headers);
+        match self.certificate_table {
+            Some((offset, size)) if offset >= end => {
+                ranges.push((end, offset - end));
+                ranges.push((offset + size, self.len - offset - size));
+            },
+            _ => ranges.push((end, self.len - end)),
+        }
+
+        ranges.retain(|&(_, size)| size != 0);
+        ranges
+    }
+}
+
+struct DigestWriter<'a, D: 'a>(&'a mut D);
+
+impl<'a, D: Digest> Write for DigestWriter<'a, D> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.update(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub fn authentihash<D: Digest, R: Read + Seek>(mut read: R) -> io::Result<Vec<u8>> {
+    let layout = try!(ImageLayout::new(&mut read));
+    let mut digest = D::new();
+
+    for (offset, size) in layout.ranges() {
+        try!(read.seek(SeekFrom::Start(offset)));
+        if try!(io::copy(&mut read.by_ref().take(size), &mut DigestWriter(&mut digest))) != size {
+            return Err(invalid_data("image truncated"))
+        }
+    }
+
+    Ok(digest.finalize().to_vec())
+}
+
+pub fn authentihash_sha1<R: Read + Seek>(read: R) -> io::Result<Vec<u8>> {
+    authentihash::<Sha1, R>(read)
+}
+
+pub fn authentihash_sha256<R: Read + Seek>(read: R) -> io::Result<Vec<u8>> {
+    authentihash::<Sha256, R>(read)
+}
+
+// The optional header CheckSum: the file summed as 16-bit words with end-around carry and
+// the field itself read as zero, plus the file length
+pub fn check_sum<R: Read + Seek>(mut read: R) -> io::Result<u32> {
+    let layout = try!(ImageLayout::new(&mut read));
+    try!(read.seek(SeekFrom::Start(0)));
+
+    let field = layout.check_sum..layout.check_sum + size_of::<u32>() as u64;
+    let mut buffer = vec![0u8; 0x10000];
+    let (mut sum, mut low, mut offset) = (0u32, 0u32, 0u64);
+    loop {
+        let count = match read.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(count) => count,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        for &byte in &buffer[..count] {
+            let byte = if offset >= field.start && offset < field.end { 0 } else { byte as u32 };
+            if offset & 1 == 0 {
+                low = byte;
+            } else {
+                sum += low | byte << 8;
+                sum = (sum & 0xffff) + (sum >> 16);
+            }
+            offset += 1;
+        }
+    }
+    if offset & 1 != 0 {
+        sum += low;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    Ok(sum.wrapping_add(offset as u32))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    pub fn from_oid(oid: &ObjectIdentifier) -> Option<Self> {
+        match *oid {
+            spc::SHA1 => Some(DigestAlgorithm::Sha1),
+            spc::SHA256 => Some(DigestAlgorithm::Sha256),
+            spc::SHA384 => Some(DigestAlgorithm::Sha384),
+            spc::SHA512 => Some(DigestAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn oid(&self) -> ObjectIdentifier {
+        match *self {
+            DigestAlgorithm::Sha1 => spc::SHA1,
+            DigestAlgorithm::Sha256 => spc::SHA256,
+            DigestAlgorithm::Sha384 => spc::SHA384,
+            DigestAlgorithm::Sha512 => spc::SHA512,
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match *self {
+            DigestAlgorithm::Sha1 => Sha1::digest(data).to_vec(),
+            DigestAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            DigestAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            DigestAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    pub fn authentihash<R: Read + Seek>(&self, read: R) -> io::Result<Vec<u8>> {
+        match *self {
+            DigestAlgorithm::Sha1 => authentihash::<Sha1, R>(read),
+            DigestAlgorithm::Sha256 => authentihash::<Sha256, R>(read),
+            DigestAlgorithm::Sha384 => authentihash::<Sha384, R>(read),
+            DigestAlgorithm::Sha512 => authentihash::<Sha512, R>(read),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use digest::Digest;
+    use sha2::Sha256;
+    use testing::{self, put_u32};
+    use super::*;
+
+    #[test]
+    fn image_hash() {
+        let mut data = testing::image();
+        let (check_sum, security) = (testing::CHECK_SUM, testing::SECURITY_DIRECTORY);
+
+        let mut expected = Sha256::new();
+        expected.update(&data[..check_sum]);
+        expected.update(&data[check_sum + 4..security]);
+        expected.update(&data[security + 8..]);
+        let expected = expected.finalize().to_vec();
+        assert_eq!(authentihash_sha256(Cursor::new(&data[..])).unwrap(), expected);
+        assert_eq!(authentihash_sha1(Cursor::new(&data[..])).unwrap().len(), 20);
+
+        // appending a certificate table and filling in CheckSum leaves the hash alone
+        data.extend(&[0x10, 0, 0, 0, 0, 2, 2, 0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        put_u32(&mut data, security, testing::LEN as u32);
+        put_u32(&mut data, security + 4, 0x10);
+        put_u32(&mut data, check_sum, 0x87654321);
+        assert_eq!(authentihash_sha256(Cursor::new(&data[..])).unwrap(), expected);
+
+        data[testing::LEN - 1] ^= 1;
+        assert!(authentihash_sha256(Cursor::new(&data[..])).unwrap() != expected);
+
+        data.truncate(0x300);
+        assert!(authentihash_sha256(Cursor::new(&data[..])).is_err());
+
+        let mut data = testing::image();
+        put_u32(&mut data, testing::CHECK_SUM - 4, security as u32 + 4);
+        assert!(ImageLayout::new(Cursor::new(&data[..])).is_err());
+    }
+
+    #[test]
+    fn image_check_sum() {
+        let mut data = testing::image();
+        assert_eq!(check_sum(Cursor::new(&data[..])).unwrap(), 0xb77a);
+
+        // an odd trailing byte counts as the low half of a word
+        data.push(1);
+        assert_eq!(check_sum(Cursor::new(&data[..])).unwrap(), 0xb77c);
+    }
+}
@@ -0,0 +1,32 @@
+extern crate winpe;
+extern crate digest;
+extern crate sha1;
+extern crate sha2;
+extern crate der;
+extern crate cms;
+extern crate x509_cert;
+extern crate rsa;
+extern crate p12_keystore;
+extern crate pod;
+
+use std::io;
+
+mod spc;
+mod hash;
+mod verify;
+mod sign;
+
+#[cfg(test)]
+mod testing;
+
+pub use hash::{authentihash, authentihash_sha1, authentihash_sha256, check_sum, ImageLayout, DigestAlgorithm};
+pub use verify::{signatures, verify, Signature, Timestamp, TimestampKind, TrustStore};
+pub use sign::{sign, Signer};
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn der_error(err: der::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
@@ -0,0 +1,318 @@
+use std::cmp;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem::size_of;
+use std::path::Path;
+use cms::builder::{SignedDataBuilder, SignerInfoBuilder};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::content_info::ContentInfo;
+use cms::signed_data::{EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfos};
+use der::{Decode, Encode};
+use der::asn1::{Any, OctetString, SetOfVec};
+use der::oid::AssociatedOid;
+use digest::Digest;
+use p12_keystore::KeyStore;
+use pod::Pod;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs1v15::{RsaSignatureAssociatedOid, Signature as RsaSignature, SigningKey};
+use rsa::pkcs8::DecodePrivateKey;
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use x509_cert::Certificate;
+use x509_cert::attr::Attribute;
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use winpe::image;
+use hash::{self, DigestAlgorithm, ImageLayout};
+use spc::{self, DigestInfo, SpcAttributeTypeAndOptionalValue, SpcIndirectDataContent};
+use verify::Timestamp;
+use {invalid_data, der_error};
+
+fn build_error<E: Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+fn attribute(oid: der::asn1::ObjectIdentifier, value: Any) -> io::Result<Attribute> {
+    Ok(Attribute {
+        oid: oid,
+        values: try!(SetOfVec::try_from(vec![value]).map_err(der_error)),
+    })
+}
+
+// An RSA key along with its certificate chain, the signing certificate first
+#[derive(Debug, Clone)]
+pub struct Signer {
+    key: RsaPrivateKey,
+    chain: Vec<Certificate>,
+}
+
+impl Signer {
+    // certificates may come in any order; the one matching key is moved to the front
+    pub fn new(key: RsaPrivateKey, mut certificates: Vec<Certificate>) -> io::Result<Self> {
+        let public = RsaPublicKey::from(&key);
+        let index = try!(certificates.iter().position(|certificate| {
+            RsaPublicKey::from_pkcs1_der(certificate.tbs_certificate.subject_public_key_info.subject_public_key.raw_bytes()).ok().as_ref() == Some(&public)
+        }).ok_or_else(|| invalid_data("no certificate matches the private key")));
+        let certificate = certificates.remove(index);
+        certificates.insert(0, certificate);
+
+        Ok(Signer {
+            key: key,
+            chain: certificates,
+        })
+    }
+
+    // an unencrypted PKCS#8 or PKCS#1 PEM key, and a PEM bundle with the signing certificate and intermediates
+    pub fn from_pem_files<P: AsRef<Path>, Q: AsRef<Path>>(key: P, certificates: Q) -> io::Result<Self> {
+        let key = try!(fs::read_to_string(key));
+        let key = try!(RsaPrivateKey::from_pkcs8_pem(&key)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&key))
+            .map_err(|_| invalid_data("unsupported private key"))
+        );
+        let certificates = try!(Certificate::load_pem_chain(&try!(fs::read(certificates))).map_err(der_error));
+
+        Signer::new(key, certificates)
+    }
+
+    pub fn from_pkcs12_file<P: AsRef<Path>>(path: P, password: &str) -> io::Result<Self> {
+        let store = try!(KeyStore::from_pkcs12(&try!(fs::read(path)), password).map_err(build_error));
+        let (_, chain) = try!(store.private_key_chain().ok_or_else(|| invalid_data("no private key in PKCS#12 file")));
+        let key = try!(RsaPrivateKey::from_pkcs8_der(chain.key()).map_err(|_| invalid_data("unsupported private key")));
+
+        let mut certificates = Vec::new();
+        for certificate in chain.chain() {
+            certificates.push(try!(Certificate::from_der(certificate.as_der()).map_err(der_error)));
+        }
+        Signer::new(key, certificates)
+    }
+
+    pub fn certificate(&self) -> &Certificate {
+        &self.chain[0]
+    }
+
+    pub fn chain(&self) -> &[Certificate] {
+        &self.chain
+    }
+
+    fn build<D>(&self, content: &EncapsulatedContentInfo, digest: DigestAlgorithm) -> io::Result<ContentInfo>
+        where D: Digest + AssociatedOid + RsaSignatureAssociatedOid
+    {
+        let key = SigningKey::<D>::new(self.key.clone());
+        let algorithm = AlgorithmIdentifierOwned { oid: digest.oid(), parameters: None };
+        let id = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: self.certificate().tbs_certificate.issuer.clone(),
+            serial_number: self.certificate().tbs_certificate.serial_number.clone(),
+        });
+
+        let mut signer = try!(SignerInfoBuilder::new(&key, id, algorithm.clone(), content, None).map_err(build_error));
+        let opus_info = try!(Any::new(der::Tag::Sequence, Vec::new()).map_err(der_error));
+        let statement_type = try!(Any::encode_from(&vec![spc::SPC_INDIVIDUAL_SP_KEY_PURPOSE]).map_err(der_error));
+        try!(signer.add_signed_attribute(try!(attribute(spc::SPC_SP_OPUS_INFO, opus_info))).map_err(build_error));
+        try!(signer.add_signed_attribute(try!(attribute(spc::SPC_STATEMENT_TYPE, statement_type))).map_err(build_error));
+
+        let mut builder = SignedDataBuilder::new(content);
+        try!(builder.add_digest_algorithm(algorithm).map_err(build_error));
+        for certificate in &self.chain {
+            try!(builder.add_certificate(CertificateChoices::Certificate(certificate.clone())).map_err(build_error));
+        }
+        try!(builder.add_signer_info::<_, RsaSignature>(signer).map_err(build_error));
+        builder.build().map_err(build_error)
+    }
+
+    // The DER SignedData for an image. PKCS#1 v1.5 signatures are deterministic, so a timestamp
+    // token requested for the signature from an earlier run over the same image still applies.
+    pub fn signed_data<R: Read + Seek>(&self, read: R, digest: DigestAlgorithm, timestamp: Option<&[u8]>) -> io::Result<Vec<u8>> {
+        let content = SpcIndirectDataContent {
+            data: SpcAttributeTypeAndOptionalValue {
+                kind: spc::SPC_PE_IMAGE_DATA,
+                value: Some(try!(spc::pe_image_data().map_err(der_error))),
+            },
+            message_digest: DigestInfo {
+                digest_algorithm: AlgorithmIdentifierOwned { oid: digest.oid(), parameters: Some(Any::null()) },
+                digest: try!(OctetString::new(try!(digest.authentihash(read))).map_err(der_error)),
+            },
+        };
+        let content = EncapsulatedContentInfo {
+            econtent_type: spc::SPC_INDIRECT_DATA,
+            econtent: Some(try!(Any::encode_from(&content).map_err(der_error))),
+        };
+
+        let content_info = try!(match digest {
+            DigestAlgorithm::Sha1 => self.build::<Sha1>(&content, digest),
+            DigestAlgorithm::Sha256 => self.build::<Sha256>(&content, digest),
+            DigestAlgorithm::Sha384 => self.build::<Sha384>(&content, digest),
+            DigestAlgorithm::Sha512 => self.build::<Sha512>(&content, digest),
+        });
+        let token = match timestamp {
+            Some(token) => token,
+            None => return content_info.to_der().map_err(der_error),
+        };
+
+        let mut signed_data = try!(content_info.content.decode_as::<SignedData>().map_err(der_error));
+        let mut signers = signed_data.signer_infos.0.into_vec();
+        if !try!(Timestamp::from_token(token)).covers(signers[0].signature.as_bytes()) {
+            return Err(invalid_data("timestamp token is for a different signature"))
+        }
+        let token = try!(spc::timestamp_token(token).and_then(Any::from_der).map_err(der_error));
+        signers[0].unsigned_attrs = Some(try!(SetOfVec::try_from(vec![try!(attribute(spc::SPC_RFC3161_TIMESTAMP, token))]).map_err(der_error)));
+        signed_data.signer_infos = SignerInfos(try!(SetOfVec::try_from(signers).map_err(der_error)));
+
+        ContentInfo {
+            content_type: content_info.content_type,
+            content: try!(Any::encode_from(&signed_data).map_err(der_error)),
+        }.to_der().map_err(der_error)
+    }
+}
+
+// The file as it reads once padded out to length with zeroes, so it can be hashed before anything
+// is written to it
+struct Padded<R> {
+    read: R,
+    len: u64,
+    padded: u64,
+    pos: u64,
+}
+
+impl<R: Read + Seek> Read for Padded<R> {
+    fn read(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        let count = if self.pos < self.len {
+            let count = cmp::min(data.len() as u64, self.len - self.pos) as usize;
+            try!(self.read.read(&mut data[..count]))
+        } else {
+            let count = cmp::min(data.len() as u64, self.padded.saturating_sub(self.pos)) as usize;
+            for byte in &mut data[..count] {
+                *byte = 0;
+            }
+            count
+        };
+        self.pos += count as u64;
+        Ok(count)
+    }
+}
+
+impl<R: Read + Seek> Seek for Padded<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => checked_offset(self.padded, offset),
+            SeekFrom::Current(offset) => checked_offset(self.pos, offset),
+        };
+        self.pos = try!(pos.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek")));
+        if self.pos < self.len {
+            try!(self.read.seek(SeekFrom::Start(self.pos)));
+        }
+        Ok(self.pos)
+    }
+}
+
+fn checked_offset(base: u64, offset: i64) -> Option<u64> {
+    if offset < 0 {
+        base.checked_sub(offset.wrapping_neg() as u64)
+    } else {
+        base.checked_add(offset as u64)
+    }
+}
+
+// Signs an unsigned image in place: the file is hashed as if padded out to a quadword, and once
+// the SignedData is built the padding and WIN_CERTIFICATE are appended and the Security directory
+// and CheckSum are updated to match.
+pub fn sign<F: Read + Write + Seek>(mut file: F, signer: &Signer, digest: DigestAlgorithm, timestamp: Option<&[u8]>) -> io::Result<()> {
+    let layout = try!(ImageLayout::new(&mut file));
+    if layout.certificate_table.is_some() {
+        return Err(invalid_data("image is already signed"))
+    }
+
+    let offset = (layout.len + 7) & !7;
+    let signed_data = try!(signer.signed_data(Padded {
+        read: &mut file,
+        len: layout.len,
+        padded: offset,
+        pos: 0,
+    }, digest, timestamp));
+    let length = size_of::<image::WinCertificate>() + signed_data.len();
+    if offset + length as u64 > u32::max_value() as u64 {
+        return Err(invalid_data("image too large to sign"))
+    }
+
+    let mut header: image::WinCertificate = Pod::zeroed();
+    header.length.set(length as u32);
+    header.revision.set(image::WIN_CERT_REVISION_2_0);
+    header.certificate_type.set(image::WIN_CERT_TYPE_PKCS_SIGNED_DATA);
+    let mut entry = header.as_bytes().to_vec();
+    entry.extend(signed_data);
+    entry.resize((length + 7) & !7, 0);
+    try!(file.seek(SeekFrom::Start(layout.len)));
+    try!(file.write_all(&[0u8; 8][..(offset - layout.len) as usize]));
+    try!(file.write_all(&entry));
+
+    let mut directory: image::DataDirectory = Pod::zeroed();
+    directory.virtual_address.set(offset as u32);
+    directory.size.set(entry.len() as u32);
+    try!(file.seek(SeekFrom::Start(layout.security_directory)));
+    try!(file.write_all(directory.as_bytes()));
+
+    let check_sum = try!(hash::check_sum(&mut file));
+    try!(file.seek(SeekFrom::Start(layout.check_sum)));
+    try!(file.write_all(&[check_sum as u8, (check_sum >> 8) as u8, (check_sum >> 16) as u8, (check_sum >> 24) as u8]));
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+    use testing;
+    use {check_sum, verify, DigestAlgorithm, TimestampKind, TrustStore};
+    use super::*;
+
+    fn testdata(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata").join(name)
+    }
+
+    fn store() -> TrustStore {
+        let mut store = TrustStore::new();
+        store.add(testing::certificate(testing::ROOT));
+        store
+    }
+
+    #[test]
+    fn sign_image() {
+        let signer = Signer::from_pem_files(testdata("signer.key"), testdata("signer.pem")).unwrap();
+        let mut image = testing::image();
+        image.push(0xcc);
+
+        let mut file = Cursor::new(image.clone());
+        sign(&mut file, &signer, DigestAlgorithm::Sha256, None).unwrap();
+        let signed = file.into_inner();
+        assert_eq!(signed.len() % 8, 0);
+        let sum = check_sum(Cursor::new(&signed[..])).unwrap();
+        assert_eq!(&signed[testing::CHECK_SUM..testing::CHECK_SUM + 4], &[sum as u8, (sum >> 8) as u8, (sum >> 16) as u8, (sum >> 24) as u8]);
+
+        let signatures = verify(Cursor::new(&signed[..]), &store()).unwrap();
+        assert_eq!((&signatures[0].signer, signatures[0].digest_algorithm), (signer.certificate(), DigestAlgorithm::Sha256));
+        assert!(sign(Cursor::new(signed), &signer, DigestAlgorithm::Sha256, None).is_err());
+
+        // the signature comes out the same every time, so a token for it can be supplied later
+        let mut padded = image.clone();
+        padded.resize((image.len() + 7) & !7, 0);
+        let token = testing::token(&signer.signed_data(Cursor::new(&padded[..]), DigestAlgorithm::Sha1, None).unwrap());
+        let mut file = Cursor::new(image.clone());
+        sign(&mut file, &signer, DigestAlgorithm::Sha1, Some(&token)).unwrap();
+        let signatures = verify(Cursor::new(file.into_inner()), &store()).unwrap();
+        assert_eq!(signatures[0].timestamps.iter().map(|timestamp| timestamp.kind).collect::<Vec<_>>(), vec![TimestampKind::Rfc3161]);
+
+        // a token that doesn't match fails before the file is touched
+        let mut file = Cursor::new(image.clone());
+        assert!(sign(&mut file, &signer, DigestAlgorithm::Sha256, Some(&token)).is_err());
+        assert_eq!(file.into_inner(), image);
+    }
+
+    #[test]
+    fn pkcs12() {
+        let signer = Signer::from_pkcs12_file(testdata("signer.p12"), "winpe").unwrap();
+        assert_eq!((signer.certificate(), signer.chain().len()), (&testing::certificate(testing::SIGNER), 2));
+        assert!(Signer::from_pkcs12_file(testdata("signer.p12"), "wrong").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use der::{self, Decode, Encode, Header, Reader, Sequence, SliceReader, Tag, TagNumber, Tagged};
+use der::asn1::{Any, BitString, ObjectIdentifier, OctetString};
+use x509_cert::spki::AlgorithmIdentifierOwned;
+
+pub const SPC_INDIRECT_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.4");
+pub const SPC_STATEMENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.11");
+pub const SPC_SP_OPUS_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.12");
+pub const SPC_PE_IMAGE_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.15");
+pub const SPC_INDIVIDUAL_SP_KEY_PURPOSE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.21");
+pub const SPC_NESTED_SIGNATURE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.4.1");
+pub const SPC_RFC3161_TIMESTAMP: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.3.3.1");
+
+pub const SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+pub const CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+pub const MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+pub const SIGNING_TIME: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.5");
+pub const COUNTERSIGNATURE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.6");
+pub const TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+pub const SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+pub const SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+pub const SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+pub const SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+
+pub const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+pub const SHA1_WITH_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.5");
+pub const SHA256_WITH_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+pub const SHA384_WITH_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+pub const SHA512_WITH_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+
+pub const KP_CODE_SIGNING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.3");
+pub const KP_TIME_STAMPING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.8");
+
+// DigestInfo from PKCS#1, which is also the shape of an RFC3161 MessageImprint
+#[derive(Debug, Clone, PartialEq, Eq, Sequence)]
+pub struct DigestInfo {
+    pub digest_algorithm: AlgorithmIdentifierOwned,
+    pub digest: OctetString,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Sequence)]
+pub struct SpcAttributeTypeAndOptionalValue {
+    pub kind: ObjectIdentifier,
+    #[asn1(optional = "true")]
+    pub value: Option<Any>,
+}
+
+// The eContent of an Authenticode SignedData, carrying the image hash
+#[derive(Debug, Clone, PartialEq, Eq, Sequence)]
+pub struct SpcIndirectDataContent {
+    pub data: SpcAttributeTypeAndOptionalValue,
+    pub message_digest: DigestInfo,
+}
+
+impl SpcIndirectDataContent {
+    // Authenticode stores the content as a bare SEQUENCE rather than the OCTET STRING CMS
+    // expects, but either way the message digest covers the value octets of the eContent.
+    pub fn from_econtent(econtent: &Any) -> der::Result<Self> {
+        if econtent.tag() == der::Tag::OctetString {
+            SpcIndirectDataContent::from_der(econtent.value())
+        } else {
+            econtent.decode_as()
+        }
+    }
+}
+
+// SpcPeImageData the way signtool writes it: no flags, and a file link that just says
+// "<<<Obsolete>>>" in a BMPString behind an extra explicit [0]
+pub fn pe_image_data() -> der::Result<Any> {
+    let obsolete = "<<<Obsolete>>>".encode_utf16().flat_map(|c| vec![(c >> 8) as u8, c as u8]).collect::<Vec<_>>();
+    let unicode = try!(Any::new(Tag::ContextSpecific { constructed: false, number: TagNumber::N0 }, obsolete));
+    let file = try!(Any::new(Tag::ContextSpecific { constructed: true, number: TagNumber::N2 }, try!(unicode.to_der())));
+    let link = try!(Any::new(Tag::ContextSpecific { constructed: true, number: TagNumber::N0 }, try!(file.to_der())));
+
+    let mut value = try!(try!(BitString::new(0, Vec::new())).to_der());
+    value.extend(try!(link.to_der()));
+    Any::new(Tag::Sequence, value)
+}
+
+// The encoded elements of a constructed DER value, tags and lengths included
+pub fn elements(data: &[u8]) -> der::Result<Vec<&[u8]>> {
+    let mut reader = try!(SliceReader::new(data));
+    let header = try!(Header::decode(&mut reader));
+    let mut reader = try!(SliceReader::new(try!(reader.read_slice(header.length))));
+
+    let mut elements = Vec::new();
+    while !reader.is_finished() {
+        elements.push(try!(reader.tlv_bytes()));
+    }
+    Ok(elements)
+}
+
+// The certificate table pads entries out to a quadword, so the DER value is followed by zeroes
+pub fn leading(data: &[u8]) -> der::Result<&[u8]> {
+    SliceReader::new(data).and_then(|mut reader| reader.tlv_bytes())
+}
+
+// The TimeStampToken out of either a bare token or a whole TimeStampResp from a timestamping
+// authority: PKIStatusInfo is a SEQUENCE where a ContentInfo starts with its OID.
+pub fn timestamp_token(data: &[u8]) -> der::Result<&[u8]> {
+    let data = try!(leading(data));
+    match try!(elements(data))[..] {
+        [status, token, ..] if status.first() == Some(&0x30) => Ok(token),
+        [status] if status.first() == Some(&0x30) => Err(der::Tag::Sequence.value_error()),
+        _ => Ok(data),
+    }
+}
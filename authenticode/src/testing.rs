@@ -0,0 +1,214 @@
+use std::convert::TryFrom;
+use std::io::Cursor;
+use std::time::SystemTime;
+use cms::builder::{SignedDataBuilder, SignerInfoBuilder};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::content_info::ContentInfo;
+use cms::signed_data::{EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfos};
+use der::{Decode, DecodePem, Encode, Sequence};
+use der::asn1::{Any, GeneralizedTime, ObjectIdentifier, OctetString, SetOfVec};
+use digest::Digest;
+use rsa::RsaPrivateKey;
+use rsa::pkcs1v15::{Signature as RsaSignature, SigningKey};
+use rsa::pkcs8::DecodePrivateKey;
+use sha2::Sha256;
+use x509_cert::Certificate;
+use x509_cert::attr::Attribute;
+use x509_cert::builder::Builder;
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::time::Time;
+use spc::{self, DigestInfo, SpcAttributeTypeAndOptionalValue, SpcIndirectDataContent};
+use authentihash_sha256;
+
+// A raw PE32 image: headers up to 0x200, one section at 0x200..0x400 and 0x10 bytes of
+// trailing data, with every byte outside the header fields set to a recognisable pattern.
+pub const CHECK_SUM: usize = 0x98;
+pub const SECURITY_DIRECTORY: usize = 0xd8;
+pub const LEN: usize = 0x410;
+
+pub fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&[value as u8, (value >> 8) as u8]);
+}
+
+pub fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+    put_u16(data, offset, value as u16);
+    put_u16(data, offset + 2, (value >> 16) as u16);
+}
+
+pub fn image() -> Vec<u8> {
+    let mut data = (0..LEN).map(|i| (i * 7 % 251) as u8).collect::<Vec<_>>();
+    for byte in &mut data[..0x160] {
+        *byte = 0;
+    }
+
+    data[..2].copy_from_slice(b"MZ");
+    put_u32(&mut data, 0x3c, 0x40);
+    data[0x40..0x44].copy_from_slice(b"PE\0\0");
+    put_u16(&mut data, 0x44, 0x14c);
+    put_u16(&mut data, 0x46, 1);
+    put_u16(&mut data, 0x54, 0xe0);
+    put_u16(&mut data, 0x56, 0x0102);
+
+    put_u16(&mut data, 0x58, 0x10b);
+    put_u32(&mut data, 0x74, 0x400000);
+    put_u32(&mut data, 0x78, 0x1000);
+    put_u32(&mut data, 0x7c, 0x200);
+    put_u32(&mut data, 0x90, 0x2000);
+    put_u32(&mut data, 0x94, 0x200);
+    put_u32(&mut data, CHECK_SUM, 0x12345678);
+    put_u16(&mut data, 0x9c, 3);
+    put_u32(&mut data, 0xb4, 16);
+
+    data[0x138..0x140].copy_from_slice(b".text\0\0\0");
+    put_u32(&mut data, 0x140, 0x200);
+    put_u32(&mut data, 0x144, 0x1000);
+    put_u32(&mut data, 0x148, 0x200);
+    put_u32(&mut data, 0x14c, 0x200);
+    put_u32(&mut data, 0x15c, 0x60000020);
+
+    data
+}
+
+pub const ROOT: &'static str = include_str!("../testdata/root.pem");
+pub const SIGNER: &'static str = include_str!("../testdata/signer.pem");
+pub const SIGNER_KEY: &'static str = include_str!("../testdata/signer.key");
+pub const TSA: &'static str = include_str!("../testdata/tsa.pem");
+pub const TSA_KEY: &'static str = include_str!("../testdata/tsa.key");
+
+pub fn certificate(pem: &str) -> Certificate {
+    Certificate::from_pem(pem).unwrap()
+}
+
+fn signer_info_builder<'s>(key: &'s SigningKey<Sha256>, certificate: &Certificate, content: &'s EncapsulatedContentInfo) -> SignerInfoBuilder<'s, SigningKey<Sha256>> {
+    let id = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+        issuer: certificate.tbs_certificate.issuer.clone(),
+        serial_number: certificate.tbs_certificate.serial_number.clone(),
+    });
+    let digest = AlgorithmIdentifierOwned { oid: spc::SHA256, parameters: None };
+    SignerInfoBuilder::new(key, id, digest, content, None).unwrap()
+}
+
+fn build_signed_data(content_type: ObjectIdentifier, content: Any, certificate: &str, key: &str) -> Vec<u8> {
+    let certificate = self::certificate(certificate);
+    let key = SigningKey::<Sha256>::new(RsaPrivateKey::from_pkcs8_pem(key).unwrap());
+    let content = EncapsulatedContentInfo { econtent_type: content_type, econtent: Some(content) };
+
+    SignedDataBuilder::new(&content)
+        .add_digest_algorithm(AlgorithmIdentifierOwned { oid: spc::SHA256, parameters: None }).unwrap()
+        .add_certificate(CertificateChoices::Certificate(certificate.clone())).unwrap()
+        .add_signer_info::<_, RsaSignature>(signer_info_builder(&key, &certificate, &content)).unwrap()
+        .build().unwrap()
+        .to_der().unwrap()
+}
+
+// a SHA-256 Authenticode signature over image by the test signer
+pub fn sign(image: &[u8]) -> Vec<u8> {
+    let content = SpcIndirectDataContent {
+        data: SpcAttributeTypeAndOptionalValue {
+            kind: ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.15"),
+            value: None,
+        },
+        message_digest: DigestInfo {
+            digest_algorithm: AlgorithmIdentifierOwned { oid: spc::SHA256, parameters: None },
+            digest: OctetString::new(authentihash_sha256(Cursor::new(image)).unwrap()).unwrap(),
+        },
+    };
+    build_signed_data(spc::SPC_INDIRECT_DATA, Any::encode_from(&content).unwrap(), SIGNER, SIGNER_KEY)
+}
+
+fn signature_value(signed_data: &[u8]) -> Vec<u8> {
+    let signed_data = ContentInfo::from_der(signed_data).unwrap().content.decode_as::<SignedData>().unwrap();
+    let signer = signed_data.signer_infos.0.iter().next().unwrap();
+    signer.signature.as_bytes().to_vec()
+}
+
+fn attribute<T: Encode>(oid: ObjectIdentifier, value: &T) -> Attribute {
+    Attribute {
+        oid: oid,
+        values: SetOfVec::try_from(vec![Any::from_der(&value.to_der().unwrap()).unwrap()]).unwrap(),
+    }
+}
+
+// adds a PKCS#9 countersignature by the test TSA, whose certificate goes in the outer bag
+pub fn countersign(signed_data: &[u8]) -> Vec<u8> {
+    let key = SigningKey::<Sha256>::new(RsaPrivateKey::from_pkcs8_pem(TSA_KEY).unwrap());
+    let content = EncapsulatedContentInfo {
+        econtent_type: ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1"),
+        econtent: Some(Any::encode_from(&OctetString::new(signature_value(signed_data)).unwrap()).unwrap()),
+    };
+    let mut builder = signer_info_builder(&key, &certificate(TSA), &content);
+    builder.add_signed_attribute(attribute(spc::SIGNING_TIME, &Time::GeneralTime(GeneralizedTime::from_system_time(SystemTime::now()).unwrap()))).unwrap();
+    let countersignature = attribute(spc::COUNTERSIGNATURE, &Builder::build::<RsaSignature>(builder).unwrap());
+
+    modify(signed_data, |signed_data| {
+        signed_data.certificates.as_mut().unwrap().0.insert(CertificateChoices::Certificate(certificate(TSA))).unwrap();
+        add_attribute(signed_data, countersignature)
+    })
+}
+
+#[derive(Sequence)]
+struct TstInfo {
+    version: u8,
+    policy: ObjectIdentifier,
+    message_imprint: DigestInfo,
+    serial_number: u32,
+    gen_time: GeneralizedTime,
+}
+
+// an RFC3161 timestamp token from the test TSA over the signature in signed_data
+pub fn token(signed_data: &[u8]) -> Vec<u8> {
+    let info = TstInfo {
+        version: 1,
+        policy: ObjectIdentifier::new_unwrap("1.2.3.4"),
+        message_imprint: DigestInfo {
+            digest_algorithm: AlgorithmIdentifierOwned { oid: spc::SHA256, parameters: None },
+            digest: OctetString::new(Sha256::digest(&signature_value(signed_data)).to_vec()).unwrap(),
+        },
+        serial_number: 1,
+        gen_time: GeneralizedTime::from_system_time(SystemTime::now()).unwrap(),
+    };
+    let content = Any::encode_from(&OctetString::new(info.to_der().unwrap()).unwrap()).unwrap();
+    build_signed_data(spc::TST_INFO, content, TSA, TSA_KEY)
+}
+
+pub fn timestamp_token(signed_data: &[u8]) -> Attribute {
+    attribute(spc::SPC_RFC3161_TIMESTAMP, &Any::from_der(&token(signed_data)).unwrap())
+}
+
+fn modify<F: FnOnce(&mut SignedData)>(signed_data: &[u8], f: F) -> Vec<u8> {
+    let content_info = ContentInfo::from_der(signed_data).unwrap();
+    let mut signed_data = content_info.content.decode_as::<SignedData>().unwrap();
+    f(&mut signed_data);
+
+    ContentInfo {
+        content_type: content_info.content_type,
+        content: Any::encode_from(&signed_data).unwrap(),
+    }.to_der().unwrap()
+}
+
+fn add_attribute(signed_data: &mut SignedData, attribute: Attribute) {
+    let mut signers = signed_data.signer_infos.0.clone().into_vec();
+    signers[0].unsigned_attrs.get_or_insert_with(SetOfVec::new).insert(attribute).unwrap();
+    signed_data.signer_infos = SignerInfos(SetOfVec::try_from(signers).unwrap());
+}
+
+pub fn add_unsigned_attribute(signed_data: &[u8], attribute: Attribute) -> Vec<u8> {
+    modify(signed_data, |signed_data| add_attribute(signed_data, attribute))
+}
+
+// appends signed_data to the certificate table of an image()
+pub fn embed(mut image: Vec<u8>, signed_data: &[u8]) -> Vec<u8> {
+    let offset = image.len();
+    let length = 8 + signed_data.len();
+    image.extend(&[0u8; 8]);
+    put_u32(&mut image, offset, length as u32);
+    put_u16(&mut image, offset + 4, 0x0200);
+    put_u16(&mut image, offset + 6, 0x0002);
+    image.extend(signed_data);
+    image.resize(offset + ((length + 7) & !7), 0);
+
+    let size = image.len() - offset;
+    put_u32(&mut image, SECURITY_DIRECTORY, offset as u32);
+    put_u32(&mut image, SECURITY_DIRECTORY + 4, size as u32);
+    image
+}
@@ -0,0 +1,565 @@
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use std::time::SystemTime;
+use cms::cert::CertificateChoices;
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedData, SignerIdentifier, SignerInfo};
+use der::{Decode, Encode};
+use der::asn1::{Any, GeneralizedTime, ObjectIdentifier, OctetString, SetOfVec};
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use x509_cert::Certificate;
+use x509_cert::attr::Attribute;
+use x509_cert::ext::pkix::{BasicConstraints, ExtendedKeyUsage, SubjectKeyIdentifier};
+use x509_cert::spki::SubjectPublicKeyInfoOwned;
+use x509_cert::time::Time;
+use winpe::{DirectoryEntry, File, PeHeaders};
+use hash::DigestAlgorithm;
+use spc::{self, DigestInfo, SpcIndirectDataContent};
+use {invalid_data, der_error};
+
+const MAX_CHAIN_DEPTH: usize = 8;
+const MAX_NESTING_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct TrustStore {
+    roots: Vec<Certificate>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        TrustStore::default()
+    }
+
+    pub fn roots(&self) -> &[Certificate] {
+        &self.roots
+    }
+
+    pub fn add(&mut self, certificate: Certificate) {
+        if !self.roots.contains(&certificate) {
+            self.roots.push(certificate)
+        }
+    }
+
+    // takes a DER certificate or a PEM bundle of any number of them
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        let data = try!(fs::read(path));
+        let certificates = try!(if data.starts_with(b"-----BEGIN") {
+            Certificate::load_pem_chain(&data)
+        } else {
+            Certificate::from_der(&data).map(|certificate| vec![certificate])
+        }.map_err(der_error));
+
+        let count = certificates.len();
+        for certificate in certificates {
+            self.add(certificate)
+        }
+        Ok(count)
+    }
+
+    pub fn load_directory<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        let mut count = 0;
+        for entry in try!(fs::read_dir(path)) {
+            let path = try!(entry).path();
+            let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+            match extension.as_ref().map(|e| &e[..]) {
+                Some("pem") | Some("crt") | Some("cer") | Some("der") => (),
+                _ => continue,
+            }
+
+            match self.load_file(&path) {
+                Ok(loaded) => count += loaded,
+                Err(ref e) if e.kind() == io::ErrorKind::InvalidData => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(count)
+    }
+
+    // Builds a chain from certificate up to one of the roots through the intermediates,
+    // checking every link's signature and validity at the given time.
+    pub fn verify_chain(&self, certificate: &Certificate, intermediates: &[Certificate], time: SystemTime, usage: ObjectIdentifier) -> io::Result<Vec<Certificate>> {
+        if let Some((_, usages)) = try!(certificate.tbs_certificate.get::<ExtendedKeyUsage>().map_err(der_error)) {
+            if !usages.0.contains(&usage) {
+                return Err(invalid_data("certificate not valid for this usage"))
+            }
+        }
+
+        let mut chain = vec![certificate.clone()];
+        loop {
+            let issuer = {
+                let last = chain.last().unwrap();
+                try!(check_validity(last, time));
+                if self.roots.contains(last) {
+                    return Ok(chain)
+                }
+                if chain.len() > MAX_CHAIN_DEPTH {
+                    return Err(invalid_data("certificate chain too long"))
+                }
+
+                let root = self.roots.iter().find(|root| issued_by(last, root));
+                let intermediate = intermediates.iter().find(|issuer| !chain.contains(issuer) && is_ca(issuer) && issued_by(last, issuer));
+                match root.or(intermediate) {
+                    Some(issuer) => issuer.clone(),
+                    None => return Err(invalid_data("untrusted certificate chain")),
+                }
+            };
+            chain.push(issuer);
+        }
+    }
+}
+
+fn check_validity(certificate: &Certificate, time: SystemTime) -> io::Result<()> {
+    let validity = &certificate.tbs_certificate.validity;
+    if time < validity.not_before.to_system_time() || time > validity.not_after.to_system_time() {
+        Err(invalid_data("certificate not valid at signing time"))
+    } else {
+        Ok(())
+    }
+}
+
+fn is_ca(certificate: &Certificate) -> bool {
+    match certificate.tbs_certificate.get::<BasicConstraints>() {
+        Ok(Some((_, constraints))) => constraints.ca,
+        _ => false,
+    }
+}
+
+fn issued_by(certificate: &Certificate, issuer: &Certificate) -> bool {
+    certificate.tbs_certificate.issuer == issuer.tbs_certificate.subject && certificate.tbs_certificate.to_der().ok()
+        .map(|tbs| verify_signature(&issuer.tbs_certificate.subject_public_key_info, &certificate.signature_algorithm.oid, None, &tbs, certificate.signature.raw_bytes()).is_ok())
+        .unwrap_or(false)
+}
+
+fn pkcs1v15(digest: DigestAlgorithm) -> Pkcs1v15Sign {
+    match digest {
+        DigestAlgorithm::Sha1 => Pkcs1v15Sign::new::<Sha1>(),
+        DigestAlgorithm::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+        DigestAlgorithm::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+        DigestAlgorithm::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+    }
+}
+
+// A bare rsaEncryption signature algorithm takes its digest from the surrounding SignerInfo
+fn verify_signature(key: &SubjectPublicKeyInfoOwned, algorithm: &ObjectIdentifier, digest: Option<DigestAlgorithm>, data: &[u8], signature: &[u8]) -> io::Result<()> {
+    let digest = try!(match *algorithm {
+        spc::RSA_ENCRYPTION => digest,
+        spc::SHA1_WITH_RSA_ENCRYPTION => Some(DigestAlgorithm::Sha1),
+        spc::SHA256_WITH_RSA_ENCRYPTION => Some(DigestAlgorithm::Sha256),
+        spc::SHA384_WITH_RSA_ENCRYPTION => Some(DigestAlgorithm::Sha384),
+        spc::SHA512_WITH_RSA_ENCRYPTION => Some(DigestAlgorithm::Sha512),
+        _ => None,
+    }.ok_or_else(|| invalid_data("unsupported signature algorithm")));
+
+    if key.algorithm.oid != spc::RSA_ENCRYPTION {
+        return Err(invalid_data("unsupported public key algorithm"))
+    }
+    let key = try!(RsaPublicKey::from_pkcs1_der(key.subject_public_key.raw_bytes()).map_err(|_| invalid_data("bad RSA public key")));
+    key.verify(pkcs1v15(digest), &digest.digest(data), signature).map_err(|_| invalid_data("signature mismatch"))
+}
+
+fn attribute_values<'a>(attributes: &'a Option<SetOfVec<Attribute>>, oid: ObjectIdentifier) -> Vec<&'a Any> {
+    attributes.iter()
+        .flat_map(|attributes| attributes.iter())
+        .filter(|attribute| attribute.oid == oid)
+        .flat_map(|attribute| attribute.values.iter())
+        .collect()
+}
+
+fn find_certificate<'a>(certificates: &'a [Certificate], id: &SignerIdentifier) -> io::Result<&'a Certificate> {
+    certificates.iter().find(|certificate| match *id {
+        SignerIdentifier::IssuerAndSerialNumber(ref id) =>
+            certificate.tbs_certificate.issuer == id.issuer && certificate.tbs_certificate.serial_number == id.serial_number,
+        SignerIdentifier::SubjectKeyIdentifier(ref id) => match certificate.tbs_certificate.get::<SubjectKeyIdentifier>() {
+            Ok(Some((_, ref key_id))) => key_id == id,
+            _ => false,
+        },
+    }).ok_or_else(|| invalid_data("signer certificate not found"))
+}
+
+// A SignerInfo along with its signed attributes exactly as encoded: the signature covers
+// those bytes, but SetOfVec sorts its elements when decoding.
+#[derive(Debug, Clone)]
+struct Signer {
+    info: SignerInfo,
+    signed_attrs: Option<Vec<u8>>,
+}
+
+impl Signer {
+    fn decode(data: &[u8]) -> io::Result<Self> {
+        let info = try!(SignerInfo::from_der(data).map_err(der_error));
+        let fields = try!(spc::elements(data).map_err(der_error));
+        let signed_attrs = match fields.get(3) {
+            // [0] IMPLICIT SET OF, signed as a plain SET OF
+            Some(field) if field.first() == Some(&0xa0) => {
+                let mut attrs = field.to_vec();
+                attrs[0] = 0x31;
+                Some(attrs)
+            },
+            _ => None,
+        };
+
+        Ok(Signer {
+            info: info,
+            signed_attrs: signed_attrs,
+        })
+    }
+
+    fn digest_algorithm(&self) -> io::Result<DigestAlgorithm> {
+        DigestAlgorithm::from_oid(&self.info.digest_alg.oid).ok_or_else(|| invalid_data("unsupported digest algorithm"))
+    }
+
+    fn verify(&self, content: &[u8], content_type: Option<ObjectIdentifier>, certificate: &Certificate) -> io::Result<()> {
+        let digest = try!(self.digest_algorithm());
+        let signed = match self.signed_attrs {
+            Some(ref attrs) => {
+                let message_digest = try!(attribute_values(&self.info.signed_attrs, spc::MESSAGE_DIGEST).first()
+                    .ok_or_else(|| invalid_data("message digest attribute not found"))
+                    .and_then(|value| value.decode_as::<OctetString>().map_err(der_error))
+                );
+                if message_digest.as_bytes() != &digest.digest(content)[..] {
+                    return Err(invalid_data("message digest mismatch"))
+                }
+
+                if let Some(content_type) = content_type {
+                    let actual = try!(attribute_values(&self.info.signed_attrs, spc::CONTENT_TYPE).first()
+                        .ok_or_else(|| invalid_data("content type attribute not found"))
+                        .and_then(|value| value.decode_as::<ObjectIdentifier>().map_err(der_error))
+                    );
+                    if actual != content_type {
+                        return Err(invalid_data("content type mismatch"))
+                    }
+                }
+
+                &attrs[..]
+            },
+            None => content,
+        };
+
+        verify_signature(&certificate.tbs_certificate.subject_public_key_info, &self.info.signature_algorithm.oid, Some(digest), signed, self.info.signature.as_bytes())
+    }
+}
+
+// Authenticode signatures and timestamp tokens both carry exactly one signer
+fn decode_signed_data(data: &[u8]) -> io::Result<(SignedData, Signer, Vec<Certificate>)> {
+    let content_info = try!(spc::leading(data).and_then(ContentInfo::from_der).map_err(der_error));
+    if content_info.content_type != spc::SIGNED_DATA {
+        return Err(invalid_data("not a SignedData"))
+    }
+
+    let signed_data = try!(content_info.content.decode_as::<SignedData>().map_err(der_error));
+    let encoded = try!(content_info.content.to_der().map_err(der_error));
+    let signer_infos = try!(spc::elements(&encoded).map_err(der_error)).pop()
+        .map(spc::elements)
+        .unwrap_or_else(|| Ok(Vec::new()));
+    let signer = match &try!(signer_infos.map_err(der_error))[..] {
+        [signer] => try!(Signer::decode(signer)),
+        _ => return Err(invalid_data("expected exactly one signer")),
+    };
+
+    let certificates = signed_data.certificates.iter()
+        .flat_map(|certificates| certificates.0.iter())
+        .filter_map(|certificate| match *certificate {
+            CertificateChoices::Certificate(ref certificate) => Some(certificate.clone()),
+            _ => None,
+        }).collect();
+
+    Ok((signed_data, signer, certificates))
+}
+
+fn econtent(signed_data: &SignedData) -> io::Result<&Any> {
+    signed_data.encap_content_info.econtent.as_ref().ok_or_else(|| invalid_data("SignedData has no content"))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TimestampKind {
+    // a PKCS#9 countersignature made with the timestamping authority's key
+    Countersignature,
+    // an RFC3161 timestamp token in Microsoft's unsigned attribute
+    Rfc3161,
+}
+
+#[derive(Debug, Clone)]
+pub struct Timestamp {
+    pub kind: TimestampKind,
+    pub time: SystemTime,
+    pub signer: Certificate,
+    pub certificates: Vec<Certificate>,
+    signer_info: Signer,
+    // what messageDigest covers: the countersigned signature, or the token's TSTInfo
+    content: Vec<u8>,
+    imprint: Option<DigestInfo>,
+}
+
+impl Timestamp {
+    fn countersignature(data: &[u8], certificates: &[Certificate], signature: &[u8]) -> io::Result<Self> {
+        let signer = try!(Signer::decode(data));
+        let time = try!(attribute_values(&signer.info.signed_attrs, spc::SIGNING_TIME).first()
+            .ok_or_else(|| invalid_data("countersignature has no signing time"))
+            .and_then(|value| value.to_der().and_then(|value| Time::from_der(&value)).map_err(der_error))
+        );
+        let certificate = try!(find_certificate(certificates, &signer.info.sid)).clone();
+
+        Ok(Timestamp {
+            kind: TimestampKind::Countersignature,
+            time: time.to_system_time(),
+            signer: certificate,
+            certificates: certificates.to_vec(),
+            signer_info: signer,
+            content: signature.to_vec(),
+            imprint: None,
+        })
+    }
+
+    // Takes a TimeStampToken, or a whole TimeStampResp as a timestamping authority sends it
+    pub fn from_token(data: &[u8]) -> io::Result<Self> {
+        let token = try!(spc::timestamp_token(data).map_err(der_error));
+        let (signed_data, signer, certificates) = try!(decode_signed_data(token));
+        if signed_data.encap_content_info.econtent_type != spc::TST_INFO {
+            return Err(invalid_data("not a timestamp token"))
+        }
+
+        let content = try!(econtent(&signed_data).and_then(|content| content.decode_as::<OctetString>().map_err(der_error))).into_bytes();
+        let (imprint, time) = {
+            // TSTInfo: version, policy, messageImprint, serialNumber, genTime, ...
+            let fields = try!(spc::elements(&content).map_err(der_error));
+            if fields.len() < 5 {
+                return Err(invalid_data("bad TSTInfo"))
+            }
+            (
+                try!(DigestInfo::from_der(fields[2]).map_err(der_error)),
+                try!(GeneralizedTime::from_der(fields[4]).map_err(der_error)),
+            )
+        };
+        let certificate = try!(find_certificate(&certificates, &signer.info.sid)).clone();
+
+        Ok(Timestamp {
+            kind: TimestampKind::Rfc3161,
+            time: time.to_system_time(),
+            signer: certificate,
+            certificates: certificates,
+            signer_info: signer,
+            content: content,
+            imprint: Some(imprint),
+        })
+    }
+
+    // whether this timestamps the given signature value
+    pub fn covers(&self, signature: &[u8]) -> bool {
+        match self.imprint {
+            Some(ref imprint) => DigestAlgorithm::from_oid(&imprint.digest_algorithm.oid)
+                .map(|digest| imprint.digest.as_bytes() == &digest.digest(signature)[..])
+                .unwrap_or(false),
+            None => self.content == signature,
+        }
+    }
+
+    fn verify(&self, signature: &[u8], store: &TrustStore) -> io::Result<()> {
+        if !self.covers(signature) {
+            return Err(invalid_data("timestamp imprint mismatch"))
+        }
+
+        let content_type = match self.kind {
+            TimestampKind::Rfc3161 => Some(spc::TST_INFO),
+            TimestampKind::Countersignature => None,
+        };
+        try!(self.signer_info.verify(&self.content, content_type, &self.signer));
+        store.verify_chain(&self.signer, &self.certificates, self.time, spc::KP_TIME_STAMPING).map(|_| ())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Signature {
+    pub digest_algorithm: DigestAlgorithm,
+    // the image hash the signature vouches for
+    pub digest: Vec<u8>,
+    pub signer: Certificate,
+    pub certificates: Vec<Certificate>,
+    pub timestamps: Vec<Timestamp>,
+    pub nested: Vec<Signature>,
+    signer_info: Signer,
+    content: Vec<u8>,
+}
+
+impl Signature {
+    pub fn parse(data: &[u8]) -> io::Result<Self> {
+        Signature::parse_nested(data, 0)
+    }
+
+    fn parse_nested(data: &[u8], depth: usize) -> io::Result<Self> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(invalid_data("signatures nested too deeply"))
+        }
+
+        let (signed_data, signer, certificates) = try!(decode_signed_data(data));
+        if signed_data.encap_content_info.econtent_type != spc::SPC_INDIRECT_DATA {
+            return Err(invalid_data("not an Authenticode signature"))
+        }
+
+        let econtent = try!(econtent(&signed_data));
+        let indirect = try!(SpcIndirectDataContent::from_econtent(econtent).map_err(der_error));
+        let digest_algorithm = try!(DigestAlgorithm::from_oid(&indirect.message_digest.digest_algorithm.oid)
+            .ok_or_else(|| invalid_data("unsupported digest algorithm"))
+        );
+        let certificate = try!(find_certificate(&certificates, &signer.info.sid)).clone();
+
+        let mut timestamps = Vec::new();
+        for value in attribute_values(&signer.info.unsigned_attrs, spc::COUNTERSIGNATURE) {
+            let encoded = try!(value.to_der().map_err(der_error));
+            timestamps.push(try!(Timestamp::countersignature(&encoded, &certificates, signer.info.signature.as_bytes())));
+        }
+        for value in attribute_values(&signer.info.unsigned_attrs, spc::SPC_RFC3161_TIMESTAMP) {
+            let encoded = try!(value.to_der().map_err(der_error));
+            timestamps.push(try!(Timestamp::from_token(&encoded)));
+        }
+
+        let mut nested = Vec::new();
+        for value in attribute_values(&signer.info.unsigned_attrs, spc::SPC_NESTED_SIGNATURE) {
+            let encoded = try!(value.to_der().map_err(der_error));
+            nested.push(try!(Signature::parse_nested(&encoded, depth + 1)));
+        }
+
+        Ok(Signature {
+            digest_algorithm: digest_algorithm,
+            digest: indirect.message_digest.digest.as_bytes().to_vec(),
+            signer: certificate,
+            certificates: certificates,
+            timestamps: timestamps,
+            nested: nested,
+            content: econtent.value().to_vec(),
+            signer_info: signer,
+        })
+    }
+
+    // certificates are checked at the earliest timestamp, or at the current time without one
+    pub fn signing_time(&self) -> SystemTime {
+        self.timestamps.iter().map(|timestamp| timestamp.time).min().unwrap_or_else(SystemTime::now)
+    }
+
+    // Checks the image hash, the signature itself, every timestamp and the certificate chains
+    // against the store, then the nested signatures the same way.
+    pub fn verify<R: Read + Seek>(&self, mut read: R, store: &TrustStore) -> io::Result<()> {
+        self.verify_image(&mut read, store)
+    }
+
+    fn verify_image<R: Read + Seek>(&self, read: &mut R, store: &TrustStore) -> io::Result<()> {
+        if try!(self.digest_algorithm.authentihash(&mut *read)) != self.digest {
+            return Err(invalid_data("image digest mismatch"))
+        }
+
+        try!(self.signer_info.verify(&self.content, Some(spc::SPC_INDIRECT_DATA), &self.signer));
+        for timestamp in &self.timestamps {
+            try!(timestamp.verify(self.signer_info.info.signature.as_bytes(), store));
+        }
+        try!(store.verify_chain(&self.signer, &self.certificates, self.signing_time(), spc::KP_CODE_SIGNING));
+
+        for nested in &self.nested {
+            try!(nested.verify_image(read, store));
+        }
+        Ok(())
+    }
+}
+
+pub fn signatures<R: Read + Seek>(read: R) -> io::Result<Vec<Signature>> {
+    let mut file = try!(File::new(read));
+    if file.directory_header(DirectoryEntry::Security).is_none() {
+        return Ok(Vec::new())
+    }
+
+    let mut signatures = Vec::new();
+    for certificate in try!(file.certificates()) {
+        let certificate = try!(certificate);
+        if let Some(data) = certificate.signed_data() {
+            signatures.push(try!(Signature::parse(data)));
+        }
+    }
+    Ok(signatures)
+}
+
+// Verifies every signature on the image, nested ones included; an unsigned image is an error
+pub fn verify<R: Read + Seek>(mut read: R, store: &TrustStore) -> io::Result<Vec<Signature>> {
+    let signatures = try!(signatures(&mut read));
+    if signatures.is_empty() {
+        return Err(invalid_data("image is not signed"))
+    }
+
+    for signature in &signatures {
+        try!(signature.verify(&mut read, store));
+    }
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::io::Cursor;
+    use std::path::Path;
+    use testing;
+    use super::*;
+
+    fn store() -> TrustStore {
+        let mut store = TrustStore::new();
+        store.add(testing::certificate(testing::ROOT));
+        store
+    }
+
+    #[test]
+    fn signed_image() {
+        let image = testing::image();
+        let signature = testing::sign(&image);
+        let nested = testing::sign(&image);
+        let signature = testing::add_unsigned_attribute(&signature, testing::timestamp_token(&signature));
+        let signature = testing::countersign(&signature);
+        let signature = testing::add_unsigned_attribute(&signature, Attribute {
+            oid: spc::SPC_NESTED_SIGNATURE,
+            values: SetOfVec::try_from(vec![Any::from_der(&nested).unwrap()]).unwrap(),
+        });
+        let mut signed = testing::embed(image, &signature);
+
+        let signatures = verify(Cursor::new(&signed[..]), &store()).unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert_eq!((signatures[0].digest_algorithm, signatures[0].signer.clone()), (DigestAlgorithm::Sha256, testing::certificate(testing::SIGNER)));
+        let kinds = signatures[0].timestamps.iter().map(|timestamp| timestamp.kind).collect::<Vec<_>>();
+        assert!(kinds.contains(&TimestampKind::Countersignature) && kinds.contains(&TimestampKind::Rfc3161));
+        assert_eq!(signatures[0].nested.len(), 1);
+
+        assert!(verify(Cursor::new(&signed[..]), &TrustStore::new()).is_err());
+        assert!(verify(Cursor::new(&testing::image()[..]), &store()).is_err());
+        signed[0x300] ^= 1;
+        assert!(verify(Cursor::new(&signed[..]), &store()).is_err());
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let image = testing::image();
+        let nest = |signature: &[u8]| testing::add_unsigned_attribute(&testing::sign(&image), Attribute {
+            oid: spc::SPC_NESTED_SIGNATURE,
+            values: SetOfVec::try_from(vec![Any::from_der(signature).unwrap()]).unwrap(),
+        });
+
+        let mut signature = testing::sign(&image);
+        for _ in 0..MAX_NESTING_DEPTH {
+            signature = nest(&signature);
+        }
+        assert!(Signature::parse(&signature).is_ok());
+        assert!(Signature::parse(&nest(&signature)).is_err());
+    }
+
+    #[test]
+    fn trust_store() {
+        let mut store = TrustStore::new();
+        assert_eq!(store.load_directory(Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata")).unwrap(), 3);
+        assert_eq!(store.roots().len(), 3);
+
+        let root = testing::certificate(testing::ROOT);
+        let signer = testing::certificate(testing::SIGNER);
+        let chain = self::store().verify_chain(&signer, &[], SystemTime::now(), spc::KP_CODE_SIGNING).unwrap();
+        assert_eq!(chain, vec![signer.clone(), root]);
+        assert!(self::store().verify_chain(&signer, &[], SystemTime::now(), spc::KP_TIME_STAMPING).is_err());
+        assert!(self::store().verify_chain(&testing::certificate(testing::TSA), &[], SystemTime::UNIX_EPOCH, spc::KP_TIME_STAMPING).is_err());
+    }
+}
@@ -4,7 +4,7 @@
 extern crate pod;
 extern crate byteorder_pod;
 
-use std::{str, slice, mem};
+use std::{str, slice, mem, fmt};
 use std::ffi::CStr;
 use pod::Pod;
 use pod::packed::{Unaligned, Packed};
@@ -169,6 +169,7 @@ pub const FILE_MACHINE_CEF: u16 = 0x0cef;
 pub const FILE_MACHINE_EBC: u16 = 0x0ebc;
 pub const FILE_MACHINE_AMD64: u16 = 0x8664;
 pub const FILE_MACHINE_M32R: u16 = 0x9041;
+pub const FILE_MACHINE_ARM64: u16 = 0xaa64;
 pub const FILE_MACHINE_CEE: u16 = 0xc0ee;
 
 #[repr(C)]
@@ -1064,6 +1065,1450 @@ impl ImportByName {
     }
 }
 
+pub const DELAYLOAD_RVA_BASED: u32 = 0x00000001;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DelayLoadDescriptor {
+    pub attributes: Le<u32>,
+    pub dll_name_rva: Le<u32>,
+    pub module_handle_rva: Le<u32>,
+    pub import_address_table_rva: Le<u32>,
+    pub import_name_table_rva: Le<u32>,
+    pub bound_import_address_table_rva: Le<u32>,
+    pub unload_information_table_rva: Le<u32>,
+    pub time_date_stamp: Le<u32>,
+}
+
+unsafe impl Pod for DelayLoadDescriptor { }
+unsafe impl Unaligned for DelayLoadDescriptor { }
+unsafe impl Packed for DelayLoadDescriptor { }
+
+impl DelayLoadDescriptor {
+    pub fn attributes(&self) -> u32 {
+        self.attributes.get()
+    }
+
+    pub fn rva_based(&self) -> bool {
+        self.attributes() & DELAYLOAD_RVA_BASED != 0
+    }
+
+    pub fn dll_name_rva(&self) -> u32 {
+        self.dll_name_rva.get()
+    }
+
+    pub fn module_handle_rva(&self) -> u32 {
+        self.module_handle_rva.get()
+    }
+
+    pub fn import_address_table_rva(&self) -> u32 {
+        self.import_address_table_rva.get()
+    }
+
+    pub fn import_name_table_rva(&self) -> u32 {
+        self.import_name_table_rva.get()
+    }
+
+    pub fn bound_import_address_table_rva(&self) -> u32 {
+        self.bound_import_address_table_rva.get()
+    }
+
+    pub fn unload_information_table_rva(&self) -> u32 {
+        self.unload_information_table_rva.get()
+    }
+
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoundImportDescriptor {
+    pub time_date_stamp: Le<u32>,
+    pub offset_module_name: Le<u16>,
+    pub number_of_module_forwarder_refs: Le<u16>,
+}
+
+unsafe impl Pod for BoundImportDescriptor { }
+unsafe impl Unaligned for BoundImportDescriptor { }
+unsafe impl Packed for BoundImportDescriptor { }
+
+impl BoundImportDescriptor {
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp.get()
+    }
+
+    pub fn offset_module_name(&self) -> u16 {
+        self.offset_module_name.get()
+    }
+
+    pub fn number_of_module_forwarder_refs(&self) -> u16 {
+        self.number_of_module_forwarder_refs.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoundForwarderRef {
+    pub time_date_stamp: Le<u32>,
+    pub offset_module_name: Le<u16>,
+    pub reserved: Le<u16>,
+}
+
+unsafe impl Pod for BoundForwarderRef { }
+unsafe impl Unaligned for BoundForwarderRef { }
+unsafe impl Packed for BoundForwarderRef { }
+
+impl BoundForwarderRef {
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp.get()
+    }
+
+    pub fn offset_module_name(&self) -> u16 {
+        self.offset_module_name.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExportDirectory {
+    pub characteristics: Le<u32>,
+    pub time_date_stamp: Le<u32>,
+    pub major_version: Le<u16>,
+    pub minor_version: Le<u16>,
+    pub name: Le<u32>,
+    pub base: Le<u32>,
+    pub number_of_functions: Le<u32>,
+    pub number_of_names: Le<u32>,
+    pub address_of_functions: Le<u32>,
+    pub address_of_names: Le<u32>,
+    pub address_of_name_ordinals: Le<u32>,
+}
+
+unsafe impl Pod for ExportDirectory { }
+unsafe impl Unaligned for ExportDirectory { }
+unsafe impl Packed for ExportDirectory { }
+
+impl ExportDirectory {
+    pub fn characteristics(&self) -> u32 {
+        self.characteristics.get()
+    }
+
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp.get()
+    }
+
+    pub fn major_version(&self) -> u16 {
+        self.major_version.get()
+    }
+
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version.get()
+    }
+
+    pub fn name(&self) -> u32 {
+        self.name.get()
+    }
+
+    pub fn base(&self) -> u32 {
+        self.base.get()
+    }
+
+    pub fn number_of_functions(&self) -> u32 {
+        self.number_of_functions.get()
+    }
+
+    pub fn number_of_names(&self) -> u32 {
+        self.number_of_names.get()
+    }
+
+    pub fn address_of_functions(&self) -> u32 {
+        self.address_of_functions.get()
+    }
+
+    pub fn address_of_names(&self) -> u32 {
+        self.address_of_names.get()
+    }
+
+    pub fn address_of_name_ordinals(&self) -> u32 {
+        self.address_of_name_ordinals.get()
+    }
+}
+
+pub const RESOURCE_NAME_IS_STRING: u32 = 0x80000000;
+pub const RESOURCE_DATA_IS_DIRECTORY: u32 = 0x80000000;
+
+pub const RT_CURSOR: u16 = 1;
+pub const RT_BITMAP: u16 = 2;
+pub const RT_ICON: u16 = 3;
+pub const RT_MENU: u16 = 4;
+pub const RT_DIALOG: u16 = 5;
+pub const RT_STRING: u16 = 6;
+pub const RT_FONTDIR: u16 = 7;
+pub const RT_FONT: u16 = 8;
+pub const RT_ACCELERATOR: u16 = 9;
+pub const RT_RCDATA: u16 = 10;
+pub const RT_MESSAGETABLE: u16 = 11;
+pub const RT_GROUP_CURSOR: u16 = 12;
+pub const RT_GROUP_ICON: u16 = 14;
+pub const RT_VERSION: u16 = 16;
+pub const RT_DLGINCLUDE: u16 = 17;
+pub const RT_PLUGPLAY: u16 = 19;
+pub const RT_VXD: u16 = 20;
+pub const RT_ANICURSOR: u16 = 21;
+pub const RT_ANIICON: u16 = 22;
+pub const RT_HTML: u16 = 23;
+pub const RT_MANIFEST: u16 = 24;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceDirectory {
+    pub characteristics: Le<u32>,
+    pub time_date_stamp: Le<u32>,
+    pub major_version: Le<u16>,
+    pub minor_version: Le<u16>,
+    pub number_of_named_entries: Le<u16>,
+    pub number_of_id_entries: Le<u16>,
+}
+
+unsafe impl Pod for ResourceDirectory { }
+unsafe impl Unaligned for ResourceDirectory { }
+unsafe impl Packed for ResourceDirectory { }
+
+impl ResourceDirectory {
+    pub fn characteristics(&self) -> u32 {
+        self.characteristics.get()
+    }
+
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp.get()
+    }
+
+    pub fn major_version(&self) -> u16 {
+        self.major_version.get()
+    }
+
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version.get()
+    }
+
+    pub fn number_of_named_entries(&self) -> u16 {
+        self.number_of_named_entries.get()
+    }
+
+    pub fn number_of_id_entries(&self) -> u16 {
+        self.number_of_id_entries.get()
+    }
+
+    pub fn number_of_entries(&self) -> u32 {
+        self.number_of_named_entries() as u32 + self.number_of_id_entries() as u32
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceDirectoryEntry {
+    pub name: Le<u32>,
+    pub offset_to_data: Le<u32>,
+}
+
+unsafe impl Pod for ResourceDirectoryEntry { }
+unsafe impl Unaligned for ResourceDirectoryEntry { }
+unsafe impl Packed for ResourceDirectoryEntry { }
+
+impl ResourceDirectoryEntry {
+    pub fn name(&self) -> u32 {
+        self.name.get()
+    }
+
+    pub fn name_is_string(&self) -> bool {
+        (self.name() & RESOURCE_NAME_IS_STRING) != 0
+    }
+
+    pub fn name_offset(&self) -> u32 {
+        self.name() & !RESOURCE_NAME_IS_STRING
+    }
+
+    pub fn id(&self) -> u16 {
+        self.name() as u16
+    }
+
+    pub fn offset_to_data(&self) -> u32 {
+        self.offset_to_data.get()
+    }
+
+    pub fn data_is_directory(&self) -> bool {
+        (self.offset_to_data() & RESOURCE_DATA_IS_DIRECTORY) != 0
+    }
+
+    pub fn offset_to_directory(&self) -> u32 {
+        self.offset_to_data() & !RESOURCE_DATA_IS_DIRECTORY
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceDirectoryString {
+    pub length: Le<u16>,
+}
+
+unsafe impl Pod for ResourceDirectoryString { }
+unsafe impl Unaligned for ResourceDirectoryString { }
+unsafe impl Packed for ResourceDirectoryString { }
+
+impl ResourceDirectoryString {
+    pub fn length(&self) -> u16 {
+        self.length.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceDataEntry {
+    pub offset_to_data: Le<u32>,
+    pub size: Le<u32>,
+    pub code_page: Le<u32>,
+    pub reserved: Le<u32>,
+}
+
+unsafe impl Pod for ResourceDataEntry { }
+unsafe impl Unaligned for ResourceDataEntry { }
+unsafe impl Packed for ResourceDataEntry { }
+
+impl ResourceDataEntry {
+    pub fn offset_to_data(&self) -> u32 {
+        self.offset_to_data.get()
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size.get()
+    }
+
+    pub fn code_page(&self) -> u32 {
+        self.code_page.get()
+    }
+
+    pub fn reserved(&self) -> u32 {
+        self.reserved.get()
+    }
+}
+
+pub const VS_FFI_SIGNATURE: u32 = 0xfeef04bd;
+pub const VS_FFI_STRUCVERSION: u32 = 0x00010000;
+pub const VS_FFI_FILEFLAGSMASK: u32 = 0x0000003f;
+
+pub const VS_FF_DEBUG: u32 = 0x00000001;
+pub const VS_FF_PRERELEASE: u32 = 0x00000002;
+pub const VS_FF_PATCHED: u32 = 0x00000004;
+pub const VS_FF_PRIVATEBUILD: u32 = 0x00000008;
+pub const VS_FF_INFOINFERRED: u32 = 0x00000010;
+pub const VS_FF_SPECIALBUILD: u32 = 0x00000020;
+
+pub const VOS_UNKNOWN: u32 = 0x00000000;
+pub const VOS_DOS: u32 = 0x00010000;
+pub const VOS_OS216: u32 = 0x00020000;
+pub const VOS_OS232: u32 = 0x00030000;
+pub const VOS_NT: u32 = 0x00040000;
+pub const VOS_WINCE: u32 = 0x00050000;
+pub const VOS__BASE: u32 = 0x00000000;
+pub const VOS__WINDOWS16: u32 = 0x00000001;
+pub const VOS__PM16: u32 = 0x00000002;
+pub const VOS__PM32: u32 = 0x00000003;
+pub const VOS__WINDOWS32: u32 = 0x00000004;
+pub const VOS_DOS_WINDOWS16: u32 = 0x00010001;
+pub const VOS_DOS_WINDOWS32: u32 = 0x00010004;
+pub const VOS_OS216_PM16: u32 = 0x00020002;
+pub const VOS_OS232_PM32: u32 = 0x00030003;
+pub const VOS_NT_WINDOWS32: u32 = 0x00040004;
+
+pub const VFT_UNKNOWN: u32 = 0x00000000;
+pub const VFT_APP: u32 = 0x00000001;
+pub const VFT_DLL: u32 = 0x00000002;
+pub const VFT_DRV: u32 = 0x00000003;
+pub const VFT_FONT: u32 = 0x00000004;
+pub const VFT_VXD: u32 = 0x00000005;
+pub const VFT_STATIC_LIB: u32 = 0x00000007;
+
+pub const VFT2_UNKNOWN: u32 = 0x00000000;
+pub const VFT2_DRV_PRINTER: u32 = 0x00000001;
+pub const VFT2_DRV_KEYBOARD: u32 = 0x00000002;
+pub const VFT2_DRV_LANGUAGE: u32 = 0x00000003;
+pub const VFT2_DRV_DISPLAY: u32 = 0x00000004;
+pub const VFT2_DRV_MOUSE: u32 = 0x00000005;
+pub const VFT2_DRV_NETWORK: u32 = 0x00000006;
+pub const VFT2_DRV_SYSTEM: u32 = 0x00000007;
+pub const VFT2_DRV_INSTALLABLE: u32 = 0x00000008;
+pub const VFT2_DRV_SOUND: u32 = 0x00000009;
+pub const VFT2_DRV_COMM: u32 = 0x0000000a;
+pub const VFT2_DRV_VERSIONED_PRINTER: u32 = 0x0000000c;
+pub const VFT2_FONT_RASTER: u32 = 0x00000001;
+pub const VFT2_FONT_VECTOR: u32 = 0x00000002;
+pub const VFT2_FONT_TRUETYPE: u32 = 0x00000003;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FixedFileInfo {
+    pub signature: Le<u32>,
+    pub struc_version: Le<u32>,
+    pub file_version_ms: Le<u32>,
+    pub file_version_ls: Le<u32>,
+    pub product_version_ms: Le<u32>,
+    pub product_version_ls: Le<u32>,
+    pub file_flags_mask: Le<u32>,
+    pub file_flags: Le<u32>,
+    pub file_os: Le<u32>,
+    pub file_type: Le<u32>,
+    pub file_subtype: Le<u32>,
+    pub file_date_ms: Le<u32>,
+    pub file_date_ls: Le<u32>,
+}
+
+unsafe impl Pod for FixedFileInfo { }
+unsafe impl Unaligned for FixedFileInfo { }
+unsafe impl Packed for FixedFileInfo { }
+
+impl FixedFileInfo {
+    pub fn signature(&self) -> u32 {
+        self.signature.get()
+    }
+
+    pub fn struc_version(&self) -> u32 {
+        self.struc_version.get()
+    }
+
+    pub fn file_version_ms(&self) -> u32 {
+        self.file_version_ms.get()
+    }
+
+    pub fn file_version_ls(&self) -> u32 {
+        self.file_version_ls.get()
+    }
+
+    pub fn file_version(&self) -> (u16, u16, u16, u16) {
+        let (ms, ls) = (self.file_version_ms(), self.file_version_ls());
+        ((ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16)
+    }
+
+    pub fn product_version_ms(&self) -> u32 {
+        self.product_version_ms.get()
+    }
+
+    pub fn product_version_ls(&self) -> u32 {
+        self.product_version_ls.get()
+    }
+
+    pub fn product_version(&self) -> (u16, u16, u16, u16) {
+        let (ms, ls) = (self.product_version_ms(), self.product_version_ls());
+        ((ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16)
+    }
+
+    pub fn file_flags_mask(&self) -> u32 {
+        self.file_flags_mask.get()
+    }
+
+    pub fn file_flags(&self) -> u32 {
+        self.file_flags.get()
+    }
+
+    pub fn file_os(&self) -> u32 {
+        self.file_os.get()
+    }
+
+    pub fn file_type(&self) -> u32 {
+        self.file_type.get()
+    }
+
+    pub fn file_subtype(&self) -> u32 {
+        self.file_subtype.get()
+    }
+
+    pub fn file_date_ms(&self) -> u32 {
+        self.file_date_ms.get()
+    }
+
+    pub fn file_date_ls(&self) -> u32 {
+        self.file_date_ls.get()
+    }
+
+    pub fn file_date(&self) -> u64 {
+        ((self.file_date_ms() as u64) << 32) | self.file_date_ls() as u64
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RuntimeFunction {
+    pub begin_address: Le<u32>,
+    pub end_address: Le<u32>,
+    pub unwind_info_address: Le<u32>,
+}
+
+unsafe impl Pod for RuntimeFunction { }
+unsafe impl Unaligned for RuntimeFunction { }
+unsafe impl Packed for RuntimeFunction { }
+
+impl RuntimeFunction {
+    pub fn begin_address(&self) -> u32 {
+        self.begin_address.get()
+    }
+
+    pub fn end_address(&self) -> u32 {
+        self.end_address.get()
+    }
+
+    pub fn unwind_info_address(&self) -> u32 {
+        self.unwind_info_address.get()
+    }
+}
+
+pub const UNW_FLAG_NHANDLER: u8 = 0x0;
+pub const UNW_FLAG_EHANDLER: u8 = 0x1;
+pub const UNW_FLAG_UHANDLER: u8 = 0x2;
+pub const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+pub const UWOP_PUSH_NONVOL: u8 = 0x0;
+pub const UWOP_ALLOC_LARGE: u8 = 0x1;
+pub const UWOP_ALLOC_SMALL: u8 = 0x2;
+pub const UWOP_SET_FPREG: u8 = 0x3;
+pub const UWOP_SAVE_NONVOL: u8 = 0x4;
+pub const UWOP_SAVE_NONVOL_FAR: u8 = 0x5;
+pub const UWOP_EPILOG: u8 = 0x6;
+pub const UWOP_SPARE_CODE: u8 = 0x7;
+pub const UWOP_SAVE_XMM128: u8 = 0x8;
+pub const UWOP_SAVE_XMM128_FAR: u8 = 0x9;
+pub const UWOP_PUSH_MACHFRAME: u8 = 0xa;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnwindInfo {
+    pub version_flags: u8,
+    pub size_of_prolog: u8,
+    pub count_of_codes: u8,
+    pub frame_register_offset: u8,
+}
+
+unsafe impl Pod for UnwindInfo { }
+unsafe impl Unaligned for UnwindInfo { }
+unsafe impl Packed for UnwindInfo { }
+
+impl UnwindInfo {
+    pub fn version(&self) -> u8 {
+        self.version_flags & 0x7
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.version_flags >> 3
+    }
+
+    pub fn size_of_prolog(&self) -> u8 {
+        self.size_of_prolog
+    }
+
+    pub fn count_of_codes(&self) -> u8 {
+        self.count_of_codes
+    }
+
+    pub fn frame_register(&self) -> u8 {
+        self.frame_register_offset & 0xf
+    }
+
+    pub fn frame_offset(&self) -> u8 {
+        self.frame_register_offset >> 4
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnwindCode {
+    pub code_offset: u8,
+    pub unwind_op_info: u8,
+}
+
+unsafe impl Pod for UnwindCode { }
+unsafe impl Unaligned for UnwindCode { }
+unsafe impl Packed for UnwindCode { }
+
+impl UnwindCode {
+    pub fn code_offset(&self) -> u8 {
+        self.code_offset
+    }
+
+    pub fn unwind_op(&self) -> u8 {
+        self.unwind_op_info & 0xf
+    }
+
+    pub fn op_info(&self) -> u8 {
+        self.unwind_op_info >> 4
+    }
+
+    pub fn frame_offset(&self) -> u16 {
+        self.code_offset as u16 | ((self.unwind_op_info as u16) << 8)
+    }
+}
+
+pub const PDATA_REF_TO_FULL_XDATA: u8 = 0x0;
+pub const PDATA_PACKED_UNWIND_FUNCTION: u8 = 0x1;
+pub const PDATA_PACKED_UNWIND_FRAGMENT: u8 = 0x2;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArmRuntimeFunction {
+    pub begin_address: Le<u32>,
+    pub unwind_data: Le<u32>,
+}
+
+unsafe impl Pod for ArmRuntimeFunction { }
+unsafe impl Unaligned for ArmRuntimeFunction { }
+unsafe impl Packed for ArmRuntimeFunction { }
+
+impl ArmRuntimeFunction {
+    pub fn begin_address(&self) -> u32 {
+        self.begin_address.get()
+    }
+
+    pub fn unwind_data(&self) -> u32 {
+        self.unwind_data.get()
+    }
+
+    pub fn flag(&self) -> u8 {
+        (self.unwind_data() & 0x3) as u8
+    }
+
+    pub fn exception_information(&self) -> u32 {
+        self.unwind_data() & !0x3
+    }
+}
+
+pub const DEBUG_TYPE_UNKNOWN: u32 = 0;
+pub const DEBUG_TYPE_COFF: u32 = 1;
+pub const DEBUG_TYPE_CODEVIEW: u32 = 2;
+pub const DEBUG_TYPE_FPO: u32 = 3;
+pub const DEBUG_TYPE_MISC: u32 = 4;
+pub const DEBUG_TYPE_EXCEPTION: u32 = 5;
+pub const DEBUG_TYPE_FIXUP: u32 = 6;
+pub const DEBUG_TYPE_OMAP_TO_SRC: u32 = 7;
+pub const DEBUG_TYPE_OMAP_FROM_SRC: u32 = 8;
+pub const DEBUG_TYPE_BORLAND: u32 = 9;
+pub const DEBUG_TYPE_RESERVED10: u32 = 10;
+pub const DEBUG_TYPE_CLSID: u32 = 11;
+pub const DEBUG_TYPE_VC_FEATURE: u32 = 12;
+pub const DEBUG_TYPE_POGO: u32 = 13;
+pub const DEBUG_TYPE_ILTCG: u32 = 14;
+pub const DEBUG_TYPE_MPX: u32 = 15;
+pub const DEBUG_TYPE_REPRO: u32 = 16;
+pub const DEBUG_TYPE_EX_DLLCHARACTERISTICS: u32 = 20;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DebugDirectory {
+    pub characteristics: Le<u32>,
+    pub time_date_stamp: Le<u32>,
+    pub major_version: Le<u16>,
+    pub minor_version: Le<u16>,
+    pub kind: Le<u32>,
+    pub size_of_data: Le<u32>,
+    pub address_of_raw_data: Le<u32>,
+    pub pointer_to_raw_data: Le<u32>,
+}
+
+unsafe impl Pod for DebugDirectory { }
+unsafe impl Unaligned for DebugDirectory { }
+unsafe impl Packed for DebugDirectory { }
+
+impl DebugDirectory {
+    pub fn characteristics(&self) -> u32 {
+        self.characteristics.get()
+    }
+
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp.get()
+    }
+
+    pub fn major_version(&self) -> u16 {
+        self.major_version.get()
+    }
+
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version.get()
+    }
+
+    pub fn kind(&self) -> u32 {
+        self.kind.get()
+    }
+
+    pub fn size_of_data(&self) -> u32 {
+        self.size_of_data.get()
+    }
+
+    pub fn address_of_raw_data(&self) -> u32 {
+        self.address_of_raw_data.get()
+    }
+
+    pub fn pointer_to_raw_data(&self) -> u32 {
+        self.pointer_to_raw_data.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Guid {
+    pub data1: Le<u32>,
+    pub data2: Le<u16>,
+    pub data3: Le<u16>,
+    pub data4: [u8; 8],
+}
+
+unsafe impl Pod for Guid { }
+unsafe impl Unaligned for Guid { }
+unsafe impl Packed for Guid { }
+
+impl Guid {
+    pub fn data1(&self) -> u32 {
+        self.data1.get()
+    }
+
+    pub fn data2(&self) -> u16 {
+        self.data2.get()
+    }
+
+    pub fn data3(&self) -> u16 {
+        self.data3.get()
+    }
+
+    pub fn data4(&self) -> &[u8; 8] {
+        &self.data4
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let d = &self.data4;
+        write!(f, "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+            self.data1(), self.data2(), self.data3(), d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7]
+        )
+    }
+}
+
+pub const CVINFO_PDB70_CVSIGNATURE: u32 = 0x53445352;
+pub const CVINFO_PDB20_CVSIGNATURE: u32 = 0x3031424e;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CvInfoPdb70 {
+    pub cv_signature: Le<u32>,
+    pub signature: Guid,
+    pub age: Le<u32>,
+}
+
+unsafe impl Pod for CvInfoPdb70 { }
+unsafe impl Unaligned for CvInfoPdb70 { }
+unsafe impl Packed for CvInfoPdb70 { }
+
+impl CvInfoPdb70 {
+    pub fn cv_signature(&self) -> u32 {
+        self.cv_signature.get()
+    }
+
+    pub fn signature(&self) -> &Guid {
+        &self.signature
+    }
+
+    pub fn age(&self) -> u32 {
+        self.age.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CvInfoPdb20 {
+    pub cv_signature: Le<u32>,
+    pub offset: Le<u32>,
+    pub signature: Le<u32>,
+    pub age: Le<u32>,
+}
+
+unsafe impl Pod for CvInfoPdb20 { }
+unsafe impl Unaligned for CvInfoPdb20 { }
+unsafe impl Packed for CvInfoPdb20 { }
+
+impl CvInfoPdb20 {
+    pub fn cv_signature(&self) -> u32 {
+        self.cv_signature.get()
+    }
+
+    pub fn offset(&self) -> u32 {
+        self.offset.get()
+    }
+
+    pub fn signature(&self) -> u32 {
+        self.signature.get()
+    }
+
+    pub fn age(&self) -> u32 {
+        self.age.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TlsDirectory<T: EndianConvert> {
+    pub start_address_of_raw_data: Le<T>,
+    pub end_address_of_raw_data: Le<T>,
+    pub address_of_index: Le<T>,
+    pub address_of_callbacks: Le<T>,
+    pub size_of_zero_fill: Le<u32>,
+    pub characteristics: Le<u32>,
+}
+
+unsafe impl<T: EndianConvert> Pod for TlsDirectory<T> { }
+unsafe impl<T: EndianConvert> Unaligned for TlsDirectory<T> { }
+unsafe impl<T: EndianConvert> Packed for TlsDirectory<T> { }
+
+pub type TlsDirectory32 = TlsDirectory<u32>;
+pub type TlsDirectory64 = TlsDirectory<u64>;
+
+impl<T: EndianConvert> TlsDirectory<T> {
+    pub fn start_address_of_raw_data(&self) -> T {
+        self.start_address_of_raw_data.get()
+    }
+
+    pub fn end_address_of_raw_data(&self) -> T {
+        self.end_address_of_raw_data.get()
+    }
+
+    pub fn address_of_index(&self) -> T {
+        self.address_of_index.get()
+    }
+
+    pub fn address_of_callbacks(&self) -> T {
+        self.address_of_callbacks.get()
+    }
+
+    pub fn size_of_zero_fill(&self) -> u32 {
+        self.size_of_zero_fill.get()
+    }
+
+    pub fn characteristics(&self) -> u32 {
+        self.characteristics.get()
+    }
+}
+
+impl From<TlsDirectory32> for TlsDirectory64 {
+    fn from(t: TlsDirectory32) -> Self {
+        TlsDirectory64 {
+            start_address_of_raw_data: (t.start_address_of_raw_data() as u64).into(),
+            end_address_of_raw_data: (t.end_address_of_raw_data() as u64).into(),
+            address_of_index: (t.address_of_index() as u64).into(),
+            address_of_callbacks: (t.address_of_callbacks() as u64).into(),
+            size_of_zero_fill: t.size_of_zero_fill,
+            characteristics: t.characteristics,
+        }
+    }
+}
+
+pub const GUARD_CF_INSTRUMENTED: u32 = 0x00000100;
+pub const GUARD_CFW_INSTRUMENTED: u32 = 0x00000200;
+pub const GUARD_CF_FUNCTION_TABLE_PRESENT: u32 = 0x00000400;
+pub const GUARD_SECURITY_COOKIE_UNUSED: u32 = 0x00000800;
+pub const GUARD_PROTECT_DELAYLOAD_IAT: u32 = 0x00001000;
+pub const GUARD_DELAYLOAD_IAT_IN_ITS_OWN_SECTION: u32 = 0x00002000;
+pub const GUARD_CF_EXPORT_SUPPRESSION_INFO_PRESENT: u32 = 0x00004000;
+pub const GUARD_CF_ENABLE_EXPORT_SUPPRESSION: u32 = 0x00008000;
+pub const GUARD_CF_LONGJUMP_TABLE_PRESENT: u32 = 0x00010000;
+pub const GUARD_RF_INSTRUMENTED: u32 = 0x00020000;
+pub const GUARD_RF_ENABLE: u32 = 0x00040000;
+pub const GUARD_RF_STRICT: u32 = 0x00080000;
+pub const GUARD_RETPOLINE_PRESENT: u32 = 0x00100000;
+pub const GUARD_EH_CONTINUATION_TABLE_PRESENT: u32 = 0x00400000;
+pub const GUARD_XFG_ENABLED: u32 = 0x00800000;
+pub const GUARD_CASTGUARD_PRESENT: u32 = 0x01000000;
+pub const GUARD_MEMCPY_PRESENT: u32 = 0x02000000;
+pub const GUARD_CF_FUNCTION_TABLE_SIZE_MASK: u32 = 0xf0000000;
+pub const GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT: u32 = 28;
+
+pub const GUARD_FLAG_FID_SUPPRESSED: u8 = 0x01;
+pub const GUARD_FLAG_EXPORT_SUPPRESSED: u8 = 0x02;
+pub const GUARD_FLAG_FID_LANGEXCPTHANDLER: u8 = 0x04;
+pub const GUARD_FLAG_FID_XFG: u8 = 0x08;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LoadConfigCodeIntegrity {
+    pub flags: Le<u16>,
+    pub catalog: Le<u16>,
+    pub catalog_offset: Le<u32>,
+    pub reserved: Le<u32>,
+}
+
+unsafe impl Pod for LoadConfigCodeIntegrity { }
+unsafe impl Unaligned for LoadConfigCodeIntegrity { }
+unsafe impl Packed for LoadConfigCodeIntegrity { }
+
+impl LoadConfigCodeIntegrity {
+    pub fn flags(&self) -> u16 {
+        self.flags.get()
+    }
+
+    pub fn catalog(&self) -> u16 {
+        self.catalog.get()
+    }
+
+    pub fn catalog_offset(&self) -> u32 {
+        self.catalog_offset.get()
+    }
+
+    pub fn reserved(&self) -> u32 {
+        self.reserved.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LoadConfigDirectory32 {
+    pub size: Le<u32>,
+    pub time_date_stamp: Le<u32>,
+    pub major_version: Le<u16>,
+    pub minor_version: Le<u16>,
+    pub global_flags_clear: Le<u32>,
+    pub global_flags_set: Le<u32>,
+    pub critical_section_default_timeout: Le<u32>,
+    pub de_commit_free_block_threshold: Le<u32>,
+    pub de_commit_total_free_threshold: Le<u32>,
+    pub lock_prefix_table: Le<u32>,
+    pub maximum_allocation_size: Le<u32>,
+    pub virtual_memory_threshold: Le<u32>,
+    pub process_heap_flags: Le<u32>,
+    pub process_affinity_mask: Le<u32>,
+    pub csd_version: Le<u16>,
+    pub dependent_load_flags: Le<u16>,
+    pub edit_list: Le<u32>,
+    pub security_cookie: Le<u32>,
+    pub se_handler_table: Le<u32>,
+    pub se_handler_count: Le<u32>,
+    pub guard_cf_check_function_pointer: Le<u32>,
+    pub guard_cf_dispatch_function_pointer: Le<u32>,
+    pub guard_cf_function_table: Le<u32>,
+    pub guard_cf_function_count: Le<u32>,
+    pub guard_flags: Le<u32>,
+    pub code_integrity: LoadConfigCodeIntegrity,
+    pub guard_address_taken_iat_entry_table: Le<u32>,
+    pub guard_address_taken_iat_entry_count: Le<u32>,
+    pub guard_long_jump_target_table: Le<u32>,
+    pub guard_long_jump_target_count: Le<u32>,
+    pub dynamic_value_reloc_table: Le<u32>,
+    pub chpe_metadata_pointer: Le<u32>,
+    pub guard_rf_failure_routine: Le<u32>,
+    pub guard_rf_failure_routine_function_pointer: Le<u32>,
+    pub dynamic_value_reloc_table_offset: Le<u32>,
+    pub dynamic_value_reloc_table_section: Le<u16>,
+    pub reserved2: Le<u16>,
+    pub guard_rf_verify_stack_pointer_function_pointer: Le<u32>,
+    pub hot_patch_table_offset: Le<u32>,
+    pub reserved3: Le<u32>,
+    pub enclave_configuration_pointer: Le<u32>,
+    pub volatile_metadata_pointer: Le<u32>,
+    pub guard_eh_continuation_table: Le<u32>,
+    pub guard_eh_continuation_count: Le<u32>,
+    pub guard_xfg_check_function_pointer: Le<u32>,
+    pub guard_xfg_dispatch_function_pointer: Le<u32>,
+    pub guard_xfg_table_dispatch_function_pointer: Le<u32>,
+    pub cast_guard_os_determined_failure_mode: Le<u32>,
+    pub guard_memcpy_function_pointer: Le<u32>,
+}
+
+unsafe impl Pod for LoadConfigDirectory32 { }
+unsafe impl Unaligned for LoadConfigDirectory32 { }
+unsafe impl Packed for LoadConfigDirectory32 { }
+
+impl LoadConfigDirectory32 {
+    pub fn size(&self) -> u32 {
+        self.size.get()
+    }
+
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp.get()
+    }
+
+    pub fn major_version(&self) -> u16 {
+        self.major_version.get()
+    }
+
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version.get()
+    }
+
+    pub fn global_flags_clear(&self) -> u32 {
+        self.global_flags_clear.get()
+    }
+
+    pub fn global_flags_set(&self) -> u32 {
+        self.global_flags_set.get()
+    }
+
+    pub fn critical_section_default_timeout(&self) -> u32 {
+        self.critical_section_default_timeout.get()
+    }
+
+    pub fn de_commit_free_block_threshold(&self) -> u32 {
+        self.de_commit_free_block_threshold.get()
+    }
+
+    pub fn de_commit_total_free_threshold(&self) -> u32 {
+        self.de_commit_total_free_threshold.get()
+    }
+
+    pub fn lock_prefix_table(&self) -> u32 {
+        self.lock_prefix_table.get()
+    }
+
+    pub fn maximum_allocation_size(&self) -> u32 {
+        self.maximum_allocation_size.get()
+    }
+
+    pub fn virtual_memory_threshold(&self) -> u32 {
+        self.virtual_memory_threshold.get()
+    }
+
+    pub fn process_heap_flags(&self) -> u32 {
+        self.process_heap_flags.get()
+    }
+
+    pub fn process_affinity_mask(&self) -> u32 {
+        self.process_affinity_mask.get()
+    }
+
+    pub fn csd_version(&self) -> u16 {
+        self.csd_version.get()
+    }
+
+    pub fn dependent_load_flags(&self) -> u16 {
+        self.dependent_load_flags.get()
+    }
+
+    pub fn edit_list(&self) -> u32 {
+        self.edit_list.get()
+    }
+
+    pub fn security_cookie(&self) -> u32 {
+        self.security_cookie.get()
+    }
+
+    pub fn se_handler_table(&self) -> u32 {
+        self.se_handler_table.get()
+    }
+
+    pub fn se_handler_count(&self) -> u32 {
+        self.se_handler_count.get()
+    }
+
+    pub fn guard_cf_check_function_pointer(&self) -> u32 {
+        self.guard_cf_check_function_pointer.get()
+    }
+
+    pub fn guard_cf_dispatch_function_pointer(&self) -> u32 {
+        self.guard_cf_dispatch_function_pointer.get()
+    }
+
+    pub fn guard_cf_function_table(&self) -> u32 {
+        self.guard_cf_function_table.get()
+    }
+
+    pub fn guard_cf_function_count(&self) -> u32 {
+        self.guard_cf_function_count.get()
+    }
+
+    pub fn guard_flags(&self) -> u32 {
+        self.guard_flags.get()
+    }
+
+    pub fn code_integrity(&self) -> &LoadConfigCodeIntegrity {
+        &self.code_integrity
+    }
+
+    pub fn guard_address_taken_iat_entry_table(&self) -> u32 {
+        self.guard_address_taken_iat_entry_table.get()
+    }
+
+    pub fn guard_address_taken_iat_entry_count(&self) -> u32 {
+        self.guard_address_taken_iat_entry_count.get()
+    }
+
+    pub fn guard_long_jump_target_table(&self) -> u32 {
+        self.guard_long_jump_target_table.get()
+    }
+
+    pub fn guard_long_jump_target_count(&self) -> u32 {
+        self.guard_long_jump_target_count.get()
+    }
+
+    pub fn dynamic_value_reloc_table(&self) -> u32 {
+        self.dynamic_value_reloc_table.get()
+    }
+
+    pub fn chpe_metadata_pointer(&self) -> u32 {
+        self.chpe_metadata_pointer.get()
+    }
+
+    pub fn guard_rf_failure_routine(&self) -> u32 {
+        self.guard_rf_failure_routine.get()
+    }
+
+    pub fn guard_rf_failure_routine_function_pointer(&self) -> u32 {
+        self.guard_rf_failure_routine_function_pointer.get()
+    }
+
+    pub fn dynamic_value_reloc_table_offset(&self) -> u32 {
+        self.dynamic_value_reloc_table_offset.get()
+    }
+
+    pub fn dynamic_value_reloc_table_section(&self) -> u16 {
+        self.dynamic_value_reloc_table_section.get()
+    }
+
+    pub fn reserved2(&self) -> u16 {
+        self.reserved2.get()
+    }
+
+    pub fn guard_rf_verify_stack_pointer_function_pointer(&self) -> u32 {
+        self.guard_rf_verify_stack_pointer_function_pointer.get()
+    }
+
+    pub fn hot_patch_table_offset(&self) -> u32 {
+        self.hot_patch_table_offset.get()
+    }
+
+    pub fn reserved3(&self) -> u32 {
+        self.reserved3.get()
+    }
+
+    pub fn enclave_configuration_pointer(&self) -> u32 {
+        self.enclave_configuration_pointer.get()
+    }
+
+    pub fn volatile_metadata_pointer(&self) -> u32 {
+        self.volatile_metadata_pointer.get()
+    }
+
+    pub fn guard_eh_continuation_table(&self) -> u32 {
+        self.guard_eh_continuation_table.get()
+    }
+
+    pub fn guard_eh_continuation_count(&self) -> u32 {
+        self.guard_eh_continuation_count.get()
+    }
+
+    pub fn guard_xfg_check_function_pointer(&self) -> u32 {
+        self.guard_xfg_check_function_pointer.get()
+    }
+
+    pub fn guard_xfg_dispatch_function_pointer(&self) -> u32 {
+        self.guard_xfg_dispatch_function_pointer.get()
+    }
+
+    pub fn guard_xfg_table_dispatch_function_pointer(&self) -> u32 {
+        self.guard_xfg_table_dispatch_function_pointer.get()
+    }
+
+    pub fn cast_guard_os_determined_failure_mode(&self) -> u32 {
+        self.cast_guard_os_determined_failure_mode.get()
+    }
+
+    pub fn guard_memcpy_function_pointer(&self) -> u32 {
+        self.guard_memcpy_function_pointer.get()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LoadConfigDirectory64 {
+    pub size: Le<u32>,
+    pub time_date_stamp: Le<u32>,
+    pub major_version: Le<u16>,
+    pub minor_version: Le<u16>,
+    pub global_flags_clear: Le<u32>,
+    pub global_flags_set: Le<u32>,
+    pub critical_section_default_timeout: Le<u32>,
+    pub de_commit_free_block_threshold: Le<u64>,
+    pub de_commit_total_free_threshold: Le<u64>,
+    pub lock_prefix_table: Le<u64>,
+    pub maximum_allocation_size: Le<u64>,
+    pub virtual_memory_threshold: Le<u64>,
+    pub process_affinity_mask: Le<u64>,
+    pub process_heap_flags: Le<u32>,
+    pub csd_version: Le<u16>,
+    pub dependent_load_flags: Le<u16>,
+    pub edit_list: Le<u64>,
+    pub security_cookie: Le<u64>,
+    pub se_handler_table: Le<u64>,
+    pub se_handler_count: Le<u64>,
+    pub guard_cf_check_function_pointer: Le<u64>,
+    pub guard_cf_dispatch_function_pointer: Le<u64>,
+    pub guard_cf_function_table: Le<u64>,
+    pub guard_cf_function_count: Le<u64>,
+    pub guard_flags: Le<u32>,
+    pub code_integrity: LoadConfigCodeIntegrity,
+    pub guard_address_taken_iat_entry_table: Le<u64>,
+    pub guard_address_taken_iat_entry_count: Le<u64>,
+    pub guard_long_jump_target_table: Le<u64>,
+    pub guard_long_jump_target_count: Le<u64>,
+    pub dynamic_value_reloc_table: Le<u64>,
+    pub chpe_metadata_pointer: Le<u64>,
+    pub guard_rf_failure_routine: Le<u64>,
+    pub guard_rf_failure_routine_function_pointer: Le<u64>,
+    pub dynamic_value_reloc_table_offset: Le<u32>,
+    pub dynamic_value_reloc_table_section: Le<u16>,
+    pub reserved2: Le<u16>,
+    pub guard_rf_verify_stack_pointer_function_pointer: Le<u64>,
+    pub hot_patch_table_offset: Le<u32>,
+    pub reserved3: Le<u32>,
+    pub enclave_configuration_pointer: Le<u64>,
+    pub volatile_metadata_pointer: Le<u64>,
+    pub guard_eh_continuation_table: Le<u64>,
+    pub guard_eh_continuation_count: Le<u64>,
+    pub guard_xfg_check_function_pointer: Le<u64>,
+    pub guard_xfg_dispatch_function_pointer: Le<u64>,
+    pub guard_xfg_table_dispatch_function_pointer: Le<u64>,
+    pub cast_guard_os_determined_failure_mode: Le<u64>,
+    pub guard_memcpy_function_pointer: Le<u64>,
+}
+
+unsafe impl Pod for LoadConfigDirectory64 { }
+unsafe impl Unaligned for LoadConfigDirectory64 { }
+unsafe impl Packed for LoadConfigDirectory64 { }
+
+impl LoadConfigDirectory64 {
+    pub fn size(&self) -> u32 {
+        self.size.get()
+    }
+
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp.get()
+    }
+
+    pub fn major_version(&self) -> u16 {
+        self.major_version.get()
+    }
+
+    pub fn minor_version(&self) -> u16 {
+        self.minor_version.get()
+    }
+
+    pub fn global_flags_clear(&self) -> u32 {
+        self.global_flags_clear.get()
+    }
+
+    pub fn global_flags_set(&self) -> u32 {
+        self.global_flags_set.get()
+    }
+
+    pub fn critical_section_default_timeout(&self) -> u32 {
+        self.critical_section_default_timeout.get()
+    }
+
+    pub fn de_commit_free_block_threshold(&self) -> u64 {
+        self.de_commit_free_block_threshold.get()
+    }
+
+    pub fn de_commit_total_free_threshold(&self) -> u64 {
+        self.de_commit_total_free_threshold.get()
+    }
+
+    pub fn lock_prefix_table(&self) -> u64 {
+        self.lock_prefix_table.get()
+    }
+
+    pub fn maximum_allocation_size(&self) -> u64 {
+        self.maximum_allocation_size.get()
+    }
+
+    pub fn virtual_memory_threshold(&self) -> u64 {
+        self.virtual_memory_threshold.get()
+    }
+
+    pub fn process_affinity_mask(&self) -> u64 {
+        self.process_affinity_mask.get()
+    }
+
+    pub fn process_heap_flags(&self) -> u32 {
+        self.process_heap_flags.get()
+    }
+
+    pub fn csd_version(&self) -> u16 {
+        self.csd_version.get()
+    }
+
+    pub fn dependent_load_flags(&self) -> u16 {
+        self.dependent_load_flags.get()
+    }
+
+    pub fn edit_list(&self) -> u64 {
+        self.edit_list.get()
+    }
+
+    pub fn security_cookie(&self) -> u64 {
+        self.security_cookie.get()
+    }
+
+    pub fn se_handler_table(&self) -> u64 {
+        self.se_handler_table.get()
+    }
+
+    pub fn se_handler_count(&self) -> u64 {
+        self.se_handler_count.get()
+    }
+
+    pub fn guard_cf_check_function_pointer(&self) -> u64 {
+        self.guard_cf_check_function_pointer.get()
+    }
+
+    pub fn guard_cf_dispatch_function_pointer(&self) -> u64 {
+        self.guard_cf_dispatch_function_pointer.get()
+    }
+
+    pub fn guard_cf_function_table(&self) -> u64 {
+        self.guard_cf_function_table.get()
+    }
+
+    pub fn guard_cf_function_count(&self) -> u64 {
+        self.guard_cf_function_count.get()
+    }
+
+    pub fn guard_flags(&self) -> u32 {
+        self.guard_flags.get()
+    }
+
+    pub fn code_integrity(&self) -> &LoadConfigCodeIntegrity {
+        &self.code_integrity
+    }
+
+    pub fn guard_address_taken_iat_entry_table(&self) -> u64 {
+        self.guard_address_taken_iat_entry_table.get()
+    }
+
+    pub fn guard_address_taken_iat_entry_count(&self) -> u64 {
+        self.guard_address_taken_iat_entry_count.get()
+    }
+
+    pub fn guard_long_jump_target_table(&self) -> u64 {
+        self.guard_long_jump_target_table.get()
+    }
+
+    pub fn guard_long_jump_target_count(&self) -> u64 {
+        self.guard_long_jump_target_count.get()
+    }
+
+    pub fn dynamic_value_reloc_table(&self) -> u64 {
+        self.dynamic_value_reloc_table.get()
+    }
+
+    pub fn chpe_metadata_pointer(&self) -> u64 {
+        self.chpe_metadata_pointer.get()
+    }
+
+    pub fn guard_rf_failure_routine(&self) -> u64 {
+        self.guard_rf_failure_routine.get()
+    }
+
+    pub fn guard_rf_failure_routine_function_pointer(&self) -> u64 {
+        self.guard_rf_failure_routine_function_pointer.get()
+    }
+
+    pub fn dynamic_value_reloc_table_offset(&self) -> u32 {
+        self.dynamic_value_reloc_table_offset.get()
+    }
+
+    pub fn dynamic_value_reloc_table_section(&self) -> u16 {
+        self.dynamic_value_reloc_table_section.get()
+    }
+
+    pub fn reserved2(&self) -> u16 {
+        self.reserved2.get()
+    }
+
+    pub fn guard_rf_verify_stack_pointer_function_pointer(&self) -> u64 {
+        self.guard_rf_verify_stack_pointer_function_pointer.get()
+    }
+
+    pub fn hot_patch_table_offset(&self) -> u32 {
+        self.hot_patch_table_offset.get()
+    }
+
+    pub fn reserved3(&self) -> u32 {
+        self.reserved3.get()
+    }
+
+    pub fn enclave_configuration_pointer(&self) -> u64 {
+        self.enclave_configuration_pointer.get()
+    }
+
+    pub fn volatile_metadata_pointer(&self) -> u64 {
+        self.volatile_metadata_pointer.get()
+    }
+
+    pub fn guard_eh_continuation_table(&self) -> u64 {
+        self.guard_eh_continuation_table.get()
+    }
+
+    pub fn guard_eh_continuation_count(&self) -> u64 {
+        self.guard_eh_continuation_count.get()
+    }
+
+    pub fn guard_xfg_check_function_pointer(&self) -> u64 {
+        self.guard_xfg_check_function_pointer.get()
+    }
+
+    pub fn guard_xfg_dispatch_function_pointer(&self) -> u64 {
+        self.guard_xfg_dispatch_function_pointer.get()
+    }
+
+    pub fn guard_xfg_table_dispatch_function_pointer(&self) -> u64 {
+        self.guard_xfg_table_dispatch_function_pointer.get()
+    }
+
+    pub fn cast_guard_os_determined_failure_mode(&self) -> u64 {
+        self.cast_guard_os_determined_failure_mode.get()
+    }
+
+    pub fn guard_memcpy_function_pointer(&self) -> u64 {
+        self.guard_memcpy_function_pointer.get()
+    }
+}
+
+pub const WIN_CERT_REVISION_1_0: u16 = 0x0100;
+pub const WIN_CERT_REVISION_2_0: u16 = 0x0200;
+
+pub const WIN_CERT_TYPE_X509: u16 = 0x0001;
+pub const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+pub const WIN_CERT_TYPE_RESERVED_1: u16 = 0x0003;
+pub const WIN_CERT_TYPE_TS_STACK_SIGNED: u16 = 0x0004;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WinCertificate {
+    pub length: Le<u32>,
+    pub revision: Le<u16>,
+    pub certificate_type: Le<u16>,
+}
+
+unsafe impl Pod for WinCertificate { }
+unsafe impl Unaligned for WinCertificate { }
+unsafe impl Packed for WinCertificate { }
+
+impl WinCertificate {
+    pub fn length(&self) -> u32 {
+        self.length.get()
+    }
+
+    pub fn revision(&self) -> u16 {
+        self.revision.get()
+    }
+
+    pub fn certificate_type(&self) -> u16 {
+        self.certificate_type.get()
+    }
+}
+
 #[test]
 fn sizes() {
     unimplemented!()
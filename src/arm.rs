@@ -0,0 +1,341 @@
+use std::io::{self, Read};
+use std::mem::size_of;
+use {nt, image, parse, traits};
+
+fn bits(value: u32, shift: u32, width: u32) -> u32 {
+    (value >> shift) & ((1 << width) - 1)
+}
+
+fn code_byte(code: &[u8], index: usize) -> io::Result<u32> {
+    code.get(index)
+        .map(|&b| b as u32)
+        .ok_or_else(|| parse::invalid_data("unwind code truncated"))
+}
+
+fn arm64_unwind_op(code: &[u8]) -> io::Result<(nt::Arm64UnwindOp, usize)> {
+    use nt::Arm64UnwindOp::*;
+
+    let b0 = try!(code_byte(code, 0));
+    let b1 = || code_byte(code, 1);
+    Ok(match b0 {
+        0x00..=0x1f => (Alloc((b0 & 0x1f) * 16), 1),
+        0x20..=0x3f => (SaveR19R20X((b0 & 0x1f) * 8), 1),
+        0x40..=0x7f => (SaveFpLr((b0 & 0x3f) * 8), 1),
+        0x80..=0xbf => (SaveFpLrX(((b0 & 0x3f) + 1) * 8), 1),
+        0xc0..=0xc7 => (Alloc(((b0 & 0x7) << 8 | try!(b1())) * 16), 2),
+        0xc8..=0xd3 => {
+            let b1 = try!(b1());
+            let register = (19 + ((b0 & 0x3) << 2 | b1 >> 6)) as u8;
+            match b0 & !0x3 {
+                0xc8 => (SaveRegP { register: register, offset: (b1 & 0x3f) * 8 }, 2),
+                0xcc => (SaveRegPX { register: register, offset: ((b1 & 0x3f) + 1) * 8 }, 2),
+                _ => (SaveReg { register: register, offset: (b1 & 0x3f) * 8 }, 2),
+            }
+        },
+        0xd4..=0xd5 => {
+            let b1 = try!(b1());
+            (SaveRegX { register: (19 + ((b0 & 0x1) << 3 | b1 >> 5)) as u8, offset: ((b1 & 0x1f) + 1) * 8 }, 2)
+        },
+        0xd6..=0xdd => {
+            let b1 = try!(b1());
+            let x = ((b0 & 0x1) << 2 | b1 >> 6) as u8;
+            let offset = (b1 & 0x3f) * 8;
+            match b0 & !0x1 {
+                0xd6 => (SaveLrPair { register: 19 + 2 * x, offset: offset }, 2),
+                0xd8 => (SaveFRegP { register: 8 + x, offset: offset }, 2),
+                0xda => (SaveFRegPX { register: 8 + x, offset: offset + 8 }, 2),
+                _ => (SaveFReg { register: 8 + x, offset: offset }, 2),
+            }
+        },
+        0xde => {
+            let b1 = try!(b1());
+            (SaveFRegX { register: (8 + (b1 >> 5)) as u8, offset: ((b1 & 0x1f) + 1) * 8 }, 2)
+        },
+        0xdf => (AllocZ(try!(b1()) as u8), 2),
+        0xe0 => {
+            let size = try!(b1()) << 16 | try!(code_byte(code, 2)) << 8 | try!(code_byte(code, 3));
+            (Alloc(size * 16), 4)
+        },
+        0xe1 => (SetFp, 1),
+        0xe2 => (AddFp(try!(b1()) * 8), 2),
+        0xe3 => (Nop, 1),
+        0xe4 => (End, 1),
+        0xe5 => (EndC, 1),
+        0xe6 => (SaveNext, 1),
+        0xe8 => (TrapFrame, 1),
+        0xe9 => (MachineFrame, 1),
+        0xea => (Context, 1),
+        0xeb => (EcContext, 1),
+        0xec => (ClearUnwoundToCall, 1),
+        0xfc => (PacSignLr, 1),
+        _ => return Err(parse::invalid_data("bad unwind code")),
+    })
+}
+
+fn arm_unwind_op(code: &[u8]) -> io::Result<(nt::ArmUnwindOp, usize)> {
+    use nt::ArmUnwindOp::*;
+
+    const LR: u16 = 1 << 14;
+    // r4 up to and including r(last)
+    let range = |last: u32| ((1u32 << (last + 1)) - (1 << 4)) as u16;
+    let lr = |flag: u32| if flag != 0 { LR } else { 0 };
+
+    let b0 = try!(code_byte(code, 0));
+    let b1 = || code_byte(code, 1);
+    Ok(match b0 {
+        0x00..=0x7f => (AddSp { size: b0 * 4, wide: false }, 1),
+        0x80..=0xbf => {
+            let registers = ((b0 & 0x1f) << 8 | try!(b1())) as u16 | lr(b0 & 0x20);
+            (Pop { registers: registers, wide: true }, 2)
+        },
+        0xc0..=0xcf => (MovSp((b0 & 0xf) as u8), 1),
+        0xd0..=0xd7 => (Pop { registers: range(4 + (b0 & 0x3)) | lr(b0 & 0x4), wide: false }, 1),
+        0xd8..=0xdf => (Pop { registers: range(8 + (b0 & 0x3)) | lr(b0 & 0x4), wide: true }, 1),
+        0xe0..=0xe7 => (VPop { first: 8, last: (8 + (b0 & 0x7)) as u8 }, 1),
+        0xe8..=0xeb => (AddSp { size: ((b0 & 0x3) << 8 | try!(b1())) * 4, wide: true }, 2),
+        0xec..=0xed => (Pop { registers: try!(b1()) as u16 | lr(b0 & 0x1), wide: false }, 2),
+        0xee => match try!(b1()) {
+            b1 @ 0x00..=0x0f => (MicrosoftSpecific(b1 as u8), 2),
+            _ => return Err(parse::invalid_data("bad unwind code")),
+        },
+        0xef => match try!(b1()) {
+            b1 @ 0x00..=0x0f => (LdrLr(b1 * 4), 2),
+            _ => return Err(parse::invalid_data("bad unwind code")),
+        },
+        0xf5 => {
+            let b1 = try!(b1());
+            (VPop { first: (b1 >> 4) as u8, last: (b1 & 0xf) as u8 }, 2)
+        },
+        0xf6 => {
+            let b1 = try!(b1());
+            (VPop { first: (16 + (b1 >> 4)) as u8, last: (16 + (b1 & 0xf)) as u8 }, 2)
+        },
+        0xf7 | 0xf9 => {
+            let size = try!(b1()) << 8 | try!(code_byte(code, 2));
+            (AddSp { size: size * 4, wide: b0 == 0xf9 }, 3)
+        },
+        0xf8 | 0xfa => {
+            let size = try!(b1()) << 16 | try!(code_byte(code, 2)) << 8 | try!(code_byte(code, 3));
+            (AddSp { size: size * 4, wide: b0 == 0xfa }, 4)
+        },
+        0xfb => (Nop { wide: false }, 1),
+        0xfc => (Nop { wide: true }, 1),
+        0xfd => (EndNop { wide: false }, 1),
+        0xfe => (EndNop { wide: true }, 1),
+        0xff => (End, 1),
+        _ => return Err(parse::invalid_data("bad unwind code")),
+    })
+}
+
+fn unwind_codes<T, F: Fn(&[u8]) -> io::Result<(T, usize)>>(code: &[u8], decode: F) -> io::Result<Vec<nt::ArmUnwindCode<T>>> {
+    let mut codes = Vec::new();
+    let mut index = 0;
+
+    while index < code.len() {
+        let (op, len) = try!(decode(&code[index..]));
+        codes.push(nt::ArmUnwindCode {
+            index: index as u16,
+            op: op,
+        });
+        index += len;
+    }
+
+    Ok(codes)
+}
+
+// ARM64 and ARMv7 .xdata share a layout, differing only in field widths and scale
+struct XdataFormat {
+    length_scale: u32,
+    epilog_count_shift: u32,
+    code_words_shift: u32,
+    code_words_width: u32,
+    armv7: bool,
+}
+
+fn parse_xdata<T, V, F>(view: &V, address: u32, format: XdataFormat, decode: F) -> io::Result<nt::ArmXdata<T>>
+    where V: traits::PeRead + ?Sized, F: Fn(&[u8]) -> io::Result<(T, usize)>
+{
+    let mut read = try!(view.segment_from(address));
+    let mut position = address;
+
+    let header = try!(parse::read_u32(&mut read));
+    position += size_of::<u32>() as u32;
+
+    let mut epilog_count = bits(header, format.epilog_count_shift, 5);
+    let mut code_words = bits(header, format.code_words_shift, format.code_words_width);
+    if epilog_count == 0 && code_words == 0 {
+        let extended = try!(parse::read_u32(&mut read));
+        position += size_of::<u32>() as u32;
+        epilog_count = bits(extended, 0, 16);
+        code_words = bits(extended, 16, 8);
+    }
+
+    let epilogs = if bits(header, 21, 1) != 0 {
+        nt::ArmEpilogs::Single(epilog_count as u16)
+    } else {
+        let mut scopes = Vec::with_capacity(epilog_count as usize);
+        for _ in 0..epilog_count {
+            let scope = try!(parse::read_u32(&mut read));
+            scopes.push(if format.armv7 {
+                nt::ArmEpilogScope {
+                    offset: bits(scope, 0, 18) * format.length_scale,
+                    condition: bits(scope, 20, 4) as u8,
+                    index: bits(scope, 24, 8) as u16,
+                }
+            } else {
+                nt::ArmEpilogScope {
+                    offset: bits(scope, 0, 18) * format.length_scale,
+                    // ARM64 epilogs are unconditional
+                    condition: 0xe,
+                    index: bits(scope, 22, 10) as u16,
+                }
+            });
+        }
+        position += epilog_count * size_of::<u32>() as u32;
+        nt::ArmEpilogs::Scopes(scopes)
+    };
+
+    let mut code = vec![0u8; code_words as usize * size_of::<u32>()];
+    try!(read.read_exact(&mut code));
+    position += code.len() as u32;
+
+    let handler = if bits(header, 20, 1) != 0 {
+        Some(nt::UnwindHandler {
+            address: try!(parse::read_u32(&mut read)),
+            data: position + size_of::<u32>() as u32,
+        })
+    } else {
+        None
+    };
+
+    Ok(nt::ArmXdata {
+        function_length: bits(header, 0, 18) * format.length_scale,
+        version: bits(header, 18, 2) as u8,
+        fragment: format.armv7 && bits(header, 22, 1) != 0,
+        epilogs: epilogs,
+        codes: try!(unwind_codes(&code, decode)),
+        handler: handler,
+    })
+}
+
+pub fn parse_arm64_unwind<T: traits::PeRead + ?Sized>(view: &T, function: &image::ArmRuntimeFunction) -> io::Result<nt::Arm64Unwind> {
+    let data = function.unwind_data();
+
+    match function.flag() {
+        image::PDATA_REF_TO_FULL_XDATA => parse_xdata(view, function.exception_information(), XdataFormat {
+            length_scale: 4,
+            epilog_count_shift: 22,
+            code_words_shift: 27,
+            code_words_width: 5,
+            armv7: false,
+        }, arm64_unwind_op).map(nt::Arm64Unwind::Xdata),
+        flag @ image::PDATA_PACKED_UNWIND_FUNCTION | flag @ image::PDATA_PACKED_UNWIND_FRAGMENT => Ok(nt::Arm64Unwind::Packed(nt::Arm64PackedUnwind {
+            fragment: flag == image::PDATA_PACKED_UNWIND_FRAGMENT,
+            function_length: bits(data, 2, 11) * 4,
+            reg_f: bits(data, 13, 3) as u8,
+            reg_i: bits(data, 16, 4) as u8,
+            homed: bits(data, 20, 1) != 0,
+            cr: bits(data, 21, 2) as u8,
+            frame_size: bits(data, 23, 9) * 16,
+        })),
+        _ => Err(parse::invalid_data("bad packed unwind flag")),
+    }
+}
+
+pub fn parse_arm_unwind<T: traits::PeRead + ?Sized>(view: &T, function: &image::ArmRuntimeFunction) -> io::Result<nt::ArmUnwind> {
+    let data = function.unwind_data();
+
+    match function.flag() {
+        image::PDATA_REF_TO_FULL_XDATA => parse_xdata(view, function.exception_information(), XdataFormat {
+            length_scale: 2,
+            epilog_count_shift: 23,
+            code_words_shift: 28,
+            code_words_width: 4,
+            armv7: true,
+        }, arm_unwind_op).map(nt::ArmUnwind::Xdata),
+        flag @ image::PDATA_PACKED_UNWIND_FUNCTION | flag @ image::PDATA_PACKED_UNWIND_FRAGMENT => Ok(nt::ArmUnwind::Packed(nt::ArmPackedUnwind {
+            fragment: flag == image::PDATA_PACKED_UNWIND_FRAGMENT,
+            function_length: bits(data, 2, 11) * 2,
+            ret: bits(data, 13, 2) as u8,
+            homed: bits(data, 15, 1) != 0,
+            reg: bits(data, 16, 3) as u8,
+            r: bits(data, 19, 1) != 0,
+            l: bits(data, 20, 1) != 0,
+            c: bits(data, 21, 1) != 0,
+            stack_adjust: bits(data, 22, 10) as u16,
+        })),
+        _ => Err(parse::invalid_data("bad packed unwind flag")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testing::{ImageBuilder, put_u32};
+    use traits::PeRead;
+    use {nt, image, View};
+    use super::*;
+
+    #[test]
+    fn arm64() {
+        let mut pdata = vec![0u8; 16];
+        put_u32(&mut pdata, 0, 0x1000);
+        put_u32(&mut pdata, 4, 1 | 8 << 2 | 2 << 16 | 3 << 21 | 4 << 23);
+        put_u32(&mut pdata, 8, 0x1100);
+        put_u32(&mut pdata, 12, 0x3000);
+
+        let mut xdata = vec![0u8; 16];
+        put_u32(&mut xdata, 0, 16 | 1 << 20 | 1 << 22 | 1 << 27);
+        put_u32(&mut xdata, 4, 14 | 1 << 22);
+        xdata[8..12].copy_from_slice(&[0xe1, 0x81, 0xe4, 0xe3]);
+        put_u32(&mut xdata, 12, 0x1200);
+
+        let image = ImageBuilder::new(nt::NtKind::Win64)
+            .machine(image::FILE_MACHINE_ARM64)
+            .directory(nt::DirectoryEntry::Exception, 0x2000, 16)
+            .section(0x1000, vec![0; 0x200])
+            .section(0x2000, pdata)
+            .section(0x3000, xdata)
+            .build();
+        let view = View::new(&image[..]).unwrap();
+        let functions = view.function_entries().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(functions.iter().map(nt::FunctionEntry::begin_address).collect::<Vec<_>>(), vec![0x1000, 0x1100]);
+        assert_eq!(view.function_unwind(&functions[0]).unwrap(), nt::FunctionUnwind::Arm64(nt::Arm64Unwind::Packed(nt::Arm64PackedUnwind {
+            fragment: false,
+            function_length: 0x20,
+            reg_f: 0,
+            reg_i: 2,
+            homed: false,
+            cr: 3,
+            frame_size: 0x40,
+        })));
+
+        let xdata = match view.function_unwind(&functions[1]).unwrap() {
+            nt::FunctionUnwind::Arm64(nt::Arm64Unwind::Xdata(xdata)) => xdata,
+            unwind => panic!("unexpected unwind data {:?}", unwind),
+        };
+        assert_eq!(xdata.function_length, 0x40);
+        assert_eq!(xdata.epilogs, nt::ArmEpilogs::Scopes(vec![nt::ArmEpilogScope { offset: 0x38, condition: 0xe, index: 1 }]));
+        assert_eq!(xdata.codes.iter().map(|c| (c.index, c.op)).collect::<Vec<_>>(), vec![
+            (0, nt::Arm64UnwindOp::SetFp),
+            (1, nt::Arm64UnwindOp::SaveFpLrX(16)),
+            (2, nt::Arm64UnwindOp::End),
+            (3, nt::Arm64UnwindOp::Nop),
+        ]);
+        assert_eq!(xdata.handler, Some(nt::UnwindHandler { address: 0x1200, data: 0x3010 }));
+    }
+
+    #[test]
+    fn arm_codes() {
+        let codes = unwind_codes(&[0xd5, 0x04, 0xf5, 0x8f, 0x80, 0x28, 0xff], arm_unwind_op).unwrap();
+
+        assert_eq!(codes.iter().map(|c| (c.index, c.op)).collect::<Vec<_>>(), vec![
+            (0, nt::ArmUnwindOp::Pop { registers: 0x4030, wide: false }),
+            (1, nt::ArmUnwindOp::AddSp { size: 16, wide: false }),
+            (2, nt::ArmUnwindOp::VPop { first: 8, last: 15 }),
+            (4, nt::ArmUnwindOp::Pop { registers: 0x0028, wide: true }),
+            (6, nt::ArmUnwindOp::End),
+        ]);
+        assert!(unwind_codes(&[0xf7, 0x01], arm_unwind_op).is_err());
+    }
+}
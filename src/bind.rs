@@ -0,0 +1,473 @@
+use std::io::{self, Read, Write};
+use std::mem::size_of;
+use std::ffi::CString;
+use pod::{Pod, PodReadExt, PodWriteExt};
+use result::OptionResultExt;
+use {nt, image, parse, traits};
+
+// module names are offsets from the start of the directory, so the whole thing is kept around
+pub struct BoundImportIterator {
+    fuse: bool,
+    offset: usize,
+    data: Vec<u8>,
+}
+
+impl BoundImportIterator {
+    pub fn new(directory: Vec<u8>) -> Self {
+        BoundImportIterator {
+            fuse: false,
+            offset: 0,
+            data: directory,
+        }
+    }
+
+    fn module_name(&self, offset: u16) -> io::Result<CString> {
+        let name = try!(self.data.get(offset as usize..)
+            .ok_or_else(|| parse::invalid_data("bound import name outside of directory"))
+        );
+        let len = try!(name.iter().position(|&c| c == 0)
+            .ok_or_else(|| parse::invalid_data("bound import name not null terminated"))
+        );
+
+        Ok(CString::new(&name[..len]).unwrap())
+    }
+
+    fn try_next(&mut self) -> io::Result<Option<nt::BoundImport>> {
+        let mut read = &self.data[self.offset..];
+        let descriptor = match try!(read.read_pod_or_none::<image::BoundImportDescriptor>()) {
+            Some(descriptor) if descriptor.offset_module_name() != 0 => descriptor,
+            _ => return Ok(None),
+        };
+
+        let mut forwarder_refs = Vec::new();
+        for _ in 0..descriptor.number_of_module_forwarder_refs() {
+            let forwarder: image::BoundForwarderRef = try!(read.read_pod());
+            forwarder_refs.push(nt::BoundForwarderRef {
+                time_date_stamp: forwarder.time_date_stamp(),
+                module: try!(self.module_name(forwarder.offset_module_name())),
+            });
+        }
+        self.offset = self.data.len() - read.len();
+
+        Ok(Some(nt::BoundImport {
+            time_date_stamp: descriptor.time_date_stamp(),
+            module: try!(self.module_name(descriptor.offset_module_name())),
+            forwarder_refs: forwarder_refs,
+        }))
+    }
+}
+
+impl Iterator for BoundImportIterator {
+    type Item = io::Result<nt::BoundImport>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+pub fn bound_imports<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<BoundImportIterator> {
+    let data = try!(view.directory_header(nt::DirectoryEntry::BoundImport)
+        .ok_or_else(|| parse::invalid_data("bound import segment not found"))
+    );
+
+    // the linker places this directory in the headers, after the section table
+    let read = try!(if view.find_section(data.virtual_address()).is_some() {
+        view.directory(data)
+    } else {
+        view.headers_segment(data.virtual_address(), data.size())
+    });
+
+    let mut directory = Vec::new();
+    try!(read.take(data.size() as u64).read_to_end(&mut directory));
+
+    Ok(BoundImportIterator::new(directory))
+}
+
+pub fn stale_bindings<T: traits::PeHeaders>(modules: &[(&str, T)], bound: &[nt::BoundImport]) -> Vec<nt::StaleBinding> {
+    let check = |module: &CString, time_date_stamp: u32| {
+        let actual = modules.iter()
+            .find(|&&(name, _)| parse::module_name_eq(name.as_bytes(), module.as_bytes()))
+            .map(|&(_, ref module)| module.nt_headers().file_header().time_date_stamp());
+
+        if actual == Some(time_date_stamp) {
+            None
+        } else {
+            Some(nt::StaleBinding {
+                module: module.clone(),
+                bound: time_date_stamp,
+                actual: actual,
+            })
+        }
+    };
+
+    let mut stale = Vec::new();
+    for import in bound {
+        stale.extend(check(&import.module, import.time_date_stamp));
+        for forwarder in &import.forwarder_refs {
+            stale.extend(check(&forwarder.module, forwarder.time_date_stamp));
+        }
+    }
+
+    stale
+}
+
+fn module_name<T: traits::PeRead>(modules: &[(&str, T)], module: usize) -> io::Result<CString> {
+    let (directory, _) = try!(parse::export_directory(&modules[module].1));
+    modules[module].1.read_cstring(directory.name())
+}
+
+fn write_at<W: traits::PeHeaders + for<'w> traits::PeWrite<'w>>(write: &mut W, rva: u32, data: &[u8]) -> io::Result<()> {
+    let section = *try!(write.find_section(rva)
+        .ok_or_else(|| parse::invalid_data("rva not found in image"))
+    );
+
+    write.write_section(&section, rva - section.virtual_address()).and_then(|mut w| w.write_all(data))
+}
+
+fn bound_directory(bound: &[nt::BoundImport]) -> io::Result<Vec<u8>> {
+    let refs = bound.iter().map(|import| 1 + import.forwarder_refs.len()).sum::<usize>();
+    let mut directory = vec![0u8; (refs + 1) * size_of::<image::BoundImportDescriptor>()];
+    let mut names: Vec<(CString, u16)> = Vec::new();
+    let mut offset = 0;
+
+    let mut entry = |directory: &mut Vec<u8>, offset: &mut usize, module: &CString, time_date_stamp: u32, forwarders: usize| -> io::Result<()> {
+        let name = match names.iter().find(|&&(ref name, _)| name == module) {
+            Some(&(_, name)) => name,
+            None => {
+                let name = directory.len();
+                if name > u16::max_value() as usize {
+                    return Err(parse::invalid_data("bound import directory too large"))
+                }
+                directory.extend(module.as_bytes_with_nul());
+                names.push((module.clone(), name as u16));
+                name as u16
+            },
+        };
+
+        let mut descriptor: image::BoundImportDescriptor = Pod::zeroed();
+        descriptor.time_date_stamp.set(time_date_stamp);
+        descriptor.offset_module_name.set(name);
+        descriptor.number_of_module_forwarder_refs.set(forwarders as u16);
+        directory[*offset..*offset + size_of::<image::BoundImportDescriptor>()].copy_from_slice(descriptor.as_bytes());
+        *offset += size_of::<image::BoundImportDescriptor>();
+        Ok(())
+    };
+
+    for import in bound {
+        try!(entry(&mut directory, &mut offset, &import.module, import.time_date_stamp, import.forwarder_refs.len()));
+        for forwarder in &import.forwarder_refs {
+            // BoundForwarderRef only differs by naming its last field Reserved
+            try!(entry(&mut directory, &mut offset, &forwarder.module, forwarder.time_date_stamp, 0));
+        }
+    }
+
+    Ok(directory)
+}
+
+// The IAT writes and forwarder references for one imported module, or None when one of its
+// imports can't be resolved, in which case bind.exe leaves the whole module unbound
+fn bind_module<R, M>(read: &R, modules: &[(&str, M)], module: usize, descriptor: &image::ImportDescriptor) -> io::Result<Option<(Vec<(u32, Vec<u8>)>, Vec<nt::BoundForwarderRef>)>>
+    where R: traits::PeRead, M: traits::PeRead
+{
+    let mut forwarder_refs: Vec<nt::BoundForwarderRef> = Vec::new();
+    let mut thunks = Vec::new();
+    for thunk in try!(read.import_table(descriptor)) {
+        let thunk = try!(thunk);
+        let export = match parse::resolve_export(modules, modules[module].0, &thunk.symbol) {
+            Ok(Some(export)) => export,
+            Ok(None) => return Ok(None),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let exporter = &modules[export.module].1;
+        if export.module != module {
+            let forwarder = nt::BoundForwarderRef {
+                time_date_stamp: exporter.nt_headers().file_header().time_date_stamp(),
+                module: try!(module_name(modules, export.module)),
+            };
+            if !forwarder_refs.contains(&forwarder) {
+                forwarder_refs.push(forwarder);
+            }
+        }
+
+        let va = exporter.nt_headers().image_base() + export.address as u64;
+        let mut value = Vec::new();
+        try!(match read.kind() {
+            nt::NtKind::Win32 => value.write_pod(&(va as u32).to_le()),
+            nt::NtKind::Win64 => value.write_pod(&va.to_le()),
+        });
+        thunks.push((thunk.iat_rva, value));
+    }
+
+    Ok(Some((thunks, forwarder_refs)))
+}
+
+// Binds the imports of read against modules the way bind.exe does, writing the result to write,
+// which must have the same layout as read (usually a copy of the same image). Imports from modules
+// that aren't supplied, that lack an import name table to rebind from later, or that import
+// something the supplied modules can't resolve are left alone.
+pub fn bind_imports<R, M, W>(read: &R, modules: &[(&str, M)], write: &mut W) -> io::Result<Vec<nt::BoundImport>>
+    where R: traits::PeRead, M: traits::PeRead, W: traits::PeHeaders + for<'w> traits::PeWrite<'w>
+{
+    let imports = *try!(read.directory_header(nt::DirectoryEntry::Import)
+        .ok_or_else(|| parse::invalid_data("import segment not found"))
+    );
+    if read.directory_headers().len() <= image::DIRECTORY_ENTRY_BOUND_IMPORT {
+        return Err(parse::invalid_data("bound import directory entry not present"))
+    }
+
+    // nothing is written until every module has been resolved, so a failure leaves write untouched
+    let mut bound = Vec::new();
+    let mut writes = Vec::new();
+    for (index, descriptor) in try!(read.imports()).enumerate() {
+        let descriptor = try!(descriptor);
+        let name = try!(read.read_cstring(descriptor.name()));
+        let resolved = match modules.iter().position(|&(module, _)| parse::module_name_eq(module.as_bytes(), name.as_bytes())) {
+            Some(module) if descriptor.original_first_thunk() != 0 =>
+                try!(bind_module(read, modules, module, &descriptor)).map(|resolved| (module, resolved)),
+            _ => None,
+        };
+
+        let address = imports.virtual_address() + (index * size_of::<image::ImportDescriptor>()) as u32;
+        let (module, thunks, forwarder_refs) = match resolved {
+            Some((module, (thunks, forwarder_refs))) => (module, thunks, forwarder_refs),
+            None => {
+                // an earlier binding of a module left out now would point into a directory that no longer has it
+                if descriptor.time_date_stamp() == !0 {
+                    writes.push((address + size_of::<u32>() as u32, vec![0; 8]));
+                }
+                continue
+            },
+        };
+        writes.extend(thunks);
+
+        // TimeDateStamp and ForwarderChain of -1 defer to the bound import directory
+        writes.push((address + size_of::<u32>() as u32, vec![0xff; 8]));
+
+        bound.push(nt::BoundImport {
+            time_date_stamp: modules[module].1.nt_headers().file_header().time_date_stamp(),
+            module: name,
+            forwarder_refs: forwarder_refs,
+        });
+    }
+
+    // with nothing bound the headers are left as they are
+    let headers = if bound.is_empty() {
+        None
+    } else {
+        let directory = try!(bound_directory(&bound));
+
+        // the directory goes in the headers right after the section table, as the linker would place it
+        let file_header = read.nt_headers().file_header().clone();
+        let sections = read.dos_header().lfanew() as usize + size_of::<u32>() + size_of::<image::FileHeader>() + file_header.size_of_optional_header() as usize;
+        let address = try!(parse::align_up((sections + file_header.number_of_sections() as usize * size_of::<image::SectionHeader>()) as u32, size_of::<u32>() as u32)
+            .ok_or_else(|| parse::invalid_data("no room for bound import directory in headers"))
+        );
+        if address as u64 + directory.len() as u64 > read.nt_headers().size_of_headers() as u64 {
+            return Err(parse::invalid_data("no room for bound import directory in headers"))
+        }
+        Some((address, directory))
+    };
+
+    for (rva, data) in writes {
+        try!(write_at(write, rva, &data));
+    }
+    let (address, directory) = match headers {
+        Some(headers) => headers,
+        None => return Ok(bound),
+    };
+    try!(write.write_headers(address).and_then(|mut w| w.write_all(&directory)));
+
+    let mut entry: image::DataDirectory = Pod::zeroed();
+    entry.virtual_address.set(address);
+    entry.size.set(directory.len() as u32);
+    let entries = read.dos_header().lfanew() as usize + size_of::<u32>() + size_of::<image::FileHeader>() + read.kind().size_of_optional_header();
+    let offset = entries + image::DIRECTORY_ENTRY_BOUND_IMPORT * size_of::<image::DataDirectory>();
+    try!(write.write_headers(offset as u32).and_then(|mut w| w.write_all(entry.as_bytes())));
+
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use testing::{ImageBuilder, ExportTarget, export_directory, put_u16, put_u32};
+    use traits::PeRead;
+    use {nt, View};
+    use super::*;
+
+    #[test]
+    fn bound_directory() {
+        let mut image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::BoundImport, 0x300, 0x48)
+            .section(0x1000, vec![0u8; 0x10])
+            .build();
+        {
+            let directory = &mut image[0x300..0x348];
+            put_u32(directory, 0, 0x11111111);
+            put_u16(directory, 4, 0x20);
+            put_u16(directory, 6, 1);
+            put_u32(directory, 8, 0x22222222);
+            put_u16(directory, 12, 0x2d);
+            put_u32(directory, 16, 0x33333333);
+            put_u16(directory, 20, 0x37);
+            directory[0x20..0x2d].copy_from_slice(b"KERNEL32.dll\0");
+            directory[0x2d..0x37].copy_from_slice(b"NTDLL.DLL\0");
+            directory[0x37..0x42].copy_from_slice(b"USER32.dll\0");
+        }
+        let view = View::new(&image[..]).unwrap();
+
+        let bound = view.bound_imports().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(bound, vec![
+            nt::BoundImport {
+                time_date_stamp: 0x11111111,
+                module: CString::new("KERNEL32.dll").unwrap(),
+                forwarder_refs: vec![nt::BoundForwarderRef {
+                    time_date_stamp: 0x22222222,
+                    module: CString::new("NTDLL.DLL").unwrap(),
+                }],
+            },
+            nt::BoundImport {
+                time_date_stamp: 0x33333333,
+                module: CString::new("USER32.dll").unwrap(),
+                forwarder_refs: Vec::new(),
+            },
+        ]);
+
+        let kernel32 = ImageBuilder::new(nt::NtKind::Win32).time_date_stamp(0x11111111).build();
+        let ntdll = ImageBuilder::new(nt::NtKind::Win32).time_date_stamp(0x44444444).build();
+        let modules = [
+            ("kernel32", View::new(&kernel32[..]).unwrap()),
+            ("ntdll.dll", View::new(&ntdll[..]).unwrap()),
+        ];
+
+        assert_eq!(stale_bindings(&modules, &bound), vec![
+            nt::StaleBinding {
+                module: CString::new("NTDLL.DLL").unwrap(),
+                bound: 0x22222222,
+                actual: Some(0x44444444),
+            },
+            nt::StaleBinding {
+                module: CString::new("USER32.dll").unwrap(),
+                bound: 0x33333333,
+                actual: None,
+            },
+        ]);
+    }
+
+    fn exporter(name: &str, time_date_stamp: u32, exports: &[(&str, ExportTarget)]) -> Vec<u8> {
+        let exports = export_directory(0x1000, name, exports);
+        ImageBuilder::new(nt::NtKind::Win32)
+            .time_date_stamp(time_date_stamp)
+            .directory(nt::DirectoryEntry::Export, 0x1000, exports.len() as u32)
+            .section(0x1000, exports)
+            .build()
+    }
+
+    // imports Foo and Bar from a.dll, and ordinal 1 from c.dll
+    fn importer() -> Vec<u8> {
+        let mut imports = vec![0u8; 0x400];
+        for (i, &value) in [0x2100, 0, 0, 0x2080, 0x2200, 0x2140, 0, 0, 0x2090, 0x2240].iter().enumerate() {
+            put_u32(&mut imports, i * 4, value);
+        }
+        imports[0x80..0x86].copy_from_slice(b"a.dll\0");
+        imports[0x90..0x96].copy_from_slice(b"c.dll\0");
+        for &thunks in &[0x100, 0x200] {
+            put_u32(&mut imports, thunks, 0x2300);
+            put_u32(&mut imports, thunks + 4, 0x2310);
+        }
+        for &thunks in &[0x140, 0x240] {
+            put_u32(&mut imports, thunks, 0x80000001);
+        }
+        imports[0x300..0x306].copy_from_slice(b"\0\0Foo\0");
+        imports[0x310..0x316].copy_from_slice(b"\0\0Bar\0");
+
+        ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::Import, 0x2000, 0x3c)
+            .section(0x2000, imports)
+            .build()
+    }
+
+    #[test]
+    fn bind() {
+        let a = exporter("A.dll", 0xaaaa0000, &[("Foo", ExportTarget::Rva(0x1100)), ("Bar", ExportTarget::Forwarder("b.Baz"))]);
+        let b = exporter("B.dll", 0xbbbb0000, &[("Baz", ExportTarget::Rva(0x1200))]);
+        let modules = [("a.dll", View::new(&a[..]).unwrap()), ("b.dll", View::new(&b[..]).unwrap())];
+
+        let image = importer();
+        let mut out = image.clone();
+
+        let bound = bind_imports(&View::new(&image[..]).unwrap(), &modules, &mut View::new(&mut out[..]).unwrap()).unwrap();
+        assert_eq!(bound, vec![nt::BoundImport {
+            time_date_stamp: 0xaaaa0000,
+            module: CString::new("a.dll").unwrap(),
+            forwarder_refs: vec![nt::BoundForwarderRef {
+                time_date_stamp: 0xbbbb0000,
+                module: CString::new("B.dll").unwrap(),
+            }],
+        }]);
+
+        let view = View::new(&out[..]).unwrap();
+        assert_eq!(view.bound_imports().unwrap().collect::<Result<Vec<_>, _>>().unwrap(), bound);
+        assert_eq!(stale_bindings(&modules, &bound), vec![]);
+
+        let descriptors = view.imports().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!((descriptors[0].time_date_stamp(), descriptors[0].forwarder_chain()), (!0, !0));
+        assert_eq!((descriptors[1].time_date_stamp(), descriptors[1].forwarder_chain()), (0, 0));
+        assert_eq!(&out[0x2200..0x220c], &[0x00, 0x11, 0x00, 0x10, 0x00, 0x12, 0x00, 0x10, 0, 0, 0, 0]);
+        assert_eq!(&out[0x2240..0x2244], &image[0x2240..0x2244]);
+
+        let thunks = view.import_table(&descriptors[0]).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(thunks.iter().map(|thunk| (thunk.int_rva, thunk.iat_rva, thunk.iat_value)).collect::<Vec<_>>(), vec![
+            (Some(0x2100), 0x2200, 0x10001100),
+            (Some(0x2104), 0x2204, 0x10001200),
+        ]);
+    }
+
+    #[test]
+    fn bind_unresolved() {
+        let missing = exporter("A.dll", 0xaaaa0000, &[("Foo", ExportTarget::Rva(0x1100))]);
+        let forwarded = exporter("A.dll", 0xaaaa0000, &[("Foo", ExportTarget::Rva(0x1100)), ("Bar", ExportTarget::Forwarder("b.Baz"))]);
+        let image = importer();
+
+        for a in &[missing, forwarded] {
+            let modules = [("a.dll", View::new(&a[..]).unwrap())];
+            let mut out = image.clone();
+
+            let bound = bind_imports(&View::new(&image[..]).unwrap(), &modules, &mut View::new(&mut out[..]).unwrap()).unwrap();
+            assert_eq!(bound, vec![]);
+            assert_eq!(out, image);
+        }
+    }
+
+    #[test]
+    fn rebind() {
+        let a = exporter("A.dll", 0xaaaa0000, &[("Foo", ExportTarget::Rva(0x1100)), ("Bar", ExportTarget::Forwarder("b.Baz"))]);
+        let b = exporter("B.dll", 0xbbbb0000, &[("Baz", ExportTarget::Rva(0x1200))]);
+        let modules = [("a.dll", View::new(&a[..]).unwrap()), ("b.dll", View::new(&b[..]).unwrap())];
+
+        let image = importer();
+        let mut bound = image.clone();
+        bind_imports(&View::new(&image[..]).unwrap(), &modules, &mut View::new(&mut bound[..]).unwrap()).unwrap();
+
+        let mut out = bound.clone();
+        bind_imports(&View::new(&bound[..]).unwrap(), &modules, &mut View::new(&mut out[..]).unwrap()).unwrap();
+        assert_eq!(out, bound);
+
+        // without b.dll the forwarded Bar can't be bound, so a.dll stops deferring to the directory
+        let mut out = bound.clone();
+        assert_eq!(bind_imports(&View::new(&bound[..]).unwrap(), &modules[..1], &mut View::new(&mut out[..]).unwrap()).unwrap(), vec![]);
+        let view = View::new(&out[..]).unwrap();
+        let descriptors = view.imports().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!((descriptors[0].time_date_stamp(), descriptors[0].forwarder_chain()), (0, 0));
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::{self, Read, Seek, SeekFrom};
+use std::mem::size_of;
+use pod::PodReadExt;
+use result::OptionResultExt;
+use {nt, image, parse, traits};
+
+// The security directory's VirtualAddress is a file offset and the table is never mapped,
+// so this reads from the raw file rather than going through PeRead.
+pub struct CertificateIterator<R> {
+    fuse: bool,
+    offset: u64,
+    end: u64,
+    read: R,
+}
+
+impl<R> CertificateIterator<R> {
+    pub fn new(read: R, offset: u64, size: u64) -> Self {
+        CertificateIterator {
+            fuse: false,
+            offset: offset,
+            end: offset + size,
+            read: read,
+        }
+    }
+}
+
+impl<R: Read + Seek> CertificateIterator<R> {
+    fn try_next(&mut self) -> io::Result<Option<nt::Certificate>> {
+        if self.offset >= self.end {
+            return Ok(None)
+        }
+
+        try!(self.read.seek(SeekFrom::Start(self.offset)));
+        let header: image::WinCertificate = try!(self.read.read_pod());
+        let length = header.length() as u64;
+        if length < size_of::<image::WinCertificate>() as u64 || self.offset + length > self.end {
+            return Err(parse::invalid_data("bad certificate length"))
+        }
+
+        let mut data = Vec::new();
+        let size = length - size_of::<image::WinCertificate>() as u64;
+        if try!(self.read.by_ref().take(size).read_to_end(&mut data)) as u64 != size {
+            return Err(parse::invalid_data("certificate table past EOF"))
+        }
+
+        // entries are quadword aligned
+        self.offset += (length + 7) & !7;
+
+        Ok(Some(nt::Certificate {
+            revision: header.revision(),
+            kind: header.certificate_type(),
+            data: data,
+        }))
+    }
+}
+
+impl<R: Read + Seek> Iterator for CertificateIterator<R> {
+    type Item = io::Result<nt::Certificate>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+pub fn certificates<T: traits::PeHeaders + ?Sized, R: Read + Seek>(headers: &T, read: R) -> io::Result<CertificateIterator<R>> {
+    headers.directory_header(nt::DirectoryEntry::Security)
+        .ok_or_else(|| parse::invalid_data("security segment not found"))
+        .map(|data| CertificateIterator::new(read, data.virtual_address() as u64, data.size() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use testing::{ImageBuilder, put_u16, put_u32};
+    use {nt, image, File};
+
+    #[test]
+    fn certificate_table() {
+        let mut data = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::Security, 0x2000, 0x28)
+            .section(0x1000, vec![0u8; 0x10])
+            .build();
+        data.resize(0x2028, 0);
+        {
+            let table = &mut data[0x2000..];
+            put_u32(table, 0, 0xd);
+            put_u16(table, 4, image::WIN_CERT_REVISION_2_0);
+            put_u16(table, 6, image::WIN_CERT_TYPE_PKCS_SIGNED_DATA);
+            table[8..0xd].copy_from_slice(b"\x30\x03\x02\x01\x01");
+            put_u32(table, 0x10, 0x18);
+            put_u16(table, 0x14, image::WIN_CERT_REVISION_1_0);
+            put_u16(table, 0x16, image::WIN_CERT_TYPE_X509);
+        }
+
+        let mut file = File::new(Cursor::new(&data[..])).unwrap();
+        let certificates = file.certificates().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(certificates.len(), 2);
+        assert_eq!(certificates[0].signed_data(), Some(&b"\x30\x03\x02\x01\x01"[..]));
+        assert_eq!((certificates[1].revision, certificates[1].kind, certificates[1].data.len()), (image::WIN_CERT_REVISION_1_0, image::WIN_CERT_TYPE_X509, 0x10));
+        assert_eq!(certificates[1].signed_data(), None);
+
+        put_u32(&mut data, 0x2010, 0x20);
+        let mut file = File::new(Cursor::new(&data[..])).unwrap();
+        assert!(file.certificates().unwrap().nth(1).unwrap().is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use std::io::{self, Read};
+use std::mem::size_of;
+use std::ffi::CString;
+use pod::{Pod, PodReadExt};
+use result::OptionResultExt;
+use {nt, image, parse};
+
+pub struct DebugIterator<R> {
+    fuse: bool,
+    read: R,
+}
+
+impl<R> DebugIterator<R> {
+    pub fn new(directories: R) -> Self {
+        DebugIterator {
+            fuse: false,
+            read: directories,
+        }
+    }
+}
+
+impl<R: Read> DebugIterator<R> {
+    fn try_next(&mut self) -> io::Result<Option<image::DebugDirectory>> {
+        self.read.read_pod_or_none::<image::DebugDirectory>()
+    }
+}
+
+impl<R: Read> Iterator for DebugIterator<R> {
+    type Item = io::Result<image::DebugDirectory>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+fn codeview_path(data: &[u8]) -> io::Result<CString> {
+    let len = try!(data.iter().position(|&c| c == 0)
+        .ok_or_else(|| parse::invalid_data("codeview path not null terminated"))
+    );
+
+    Ok(CString::new(&data[..len]).unwrap())
+}
+
+pub fn parse_codeview(data: &[u8]) -> io::Result<nt::CodeView> {
+    if data.len() < size_of::<u32>() {
+        return Err(parse::invalid_data("codeview record truncated"))
+    }
+
+    match try!(parse::read_u32(data)) {
+        image::CVINFO_PDB70_CVSIGNATURE => {
+            let size = size_of::<image::CvInfoPdb70>();
+            let info: image::CvInfoPdb70 = try!(data.get(..size).and_then(|data| Pod::merge_copy(data))
+                .ok_or_else(|| parse::invalid_data("codeview record truncated"))
+            );
+
+            Ok(nt::CodeView::Pdb70 {
+                guid: *info.signature(),
+                age: info.age(),
+                path: try!(codeview_path(&data[size..])),
+            })
+        },
+        image::CVINFO_PDB20_CVSIGNATURE => {
+            let size = size_of::<image::CvInfoPdb20>();
+            let info: image::CvInfoPdb20 = try!(data.get(..size).and_then(|data| Pod::merge_copy(data))
+                .ok_or_else(|| parse::invalid_data("codeview record truncated"))
+            );
+
+            Ok(nt::CodeView::Pdb20 {
+                signature: info.signature(),
+                age: info.age(),
+                path: try!(codeview_path(&data[size..])),
+            })
+        },
+        _ => Err(parse::invalid_data("unknown codeview signature")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testing::{ImageBuilder, put_u16, put_u32};
+    use traits::{PeHeaders, PeRead};
+    use {nt, image, View};
+    use super::*;
+
+    #[test]
+    fn codeview() {
+        let mut rsds = vec![0u8; 0x18];
+        rsds[..4].copy_from_slice(b"RSDS");
+        put_u32(&mut rsds, 4, 0x12345678);
+        put_u16(&mut rsds, 8, 0x9abc);
+        put_u16(&mut rsds, 10, 0xdef0);
+        rsds[12..20].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        put_u32(&mut rsds, 20, 3);
+        rsds.extend_from_slice(b"C:\\build\\example.pdb\0");
+
+        let mut nb10 = vec![0u8; 0x10];
+        nb10[..4].copy_from_slice(b"NB10");
+        put_u32(&mut nb10, 8, 0x3a1b2c3d);
+        put_u32(&mut nb10, 12, 7);
+        nb10.extend_from_slice(b"old.pdb\0");
+
+        let mut directories = vec![0u8; 0x38];
+        put_u32(&mut directories, 0x0c, image::DEBUG_TYPE_CODEVIEW);
+        put_u32(&mut directories, 0x10, rsds.len() as u32);
+        put_u32(&mut directories, 0x14, 0x3000);
+        put_u32(&mut directories, 0x18, 0x600);
+        put_u32(&mut directories, 0x1c + 0x0c, image::DEBUG_TYPE_CODEVIEW);
+        put_u32(&mut directories, 0x1c + 0x10, nb10.len() as u32);
+        put_u32(&mut directories, 0x1c + 0x14, 0x3100);
+
+        let mut data = rsds;
+        data.resize(0x100, 0);
+        data.extend_from_slice(&nb10);
+
+        let image = ImageBuilder::new(nt::NtKind::Win64)
+            .directory(nt::DirectoryEntry::Debug, 0x2000, 0x38)
+            .section(0x2000, directories)
+            .section(0x3000, data)
+            .build();
+        let view = View::new(&image[..]).unwrap();
+        let entries = view.debug_directories().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].pointer_to_raw_data(), 0x600);
+
+        let codeview = view.debug_codeview(&entries[0]).unwrap();
+        match codeview {
+            nt::CodeView::Pdb70 { ref guid, age, .. } => {
+                assert_eq!(guid.to_string(), "12345678-9ABC-DEF0-0102-030405060708");
+                assert_eq!(age, 3);
+            },
+            _ => panic!("expected an RSDS record"),
+        }
+        assert_eq!(codeview.path().to_bytes(), b"C:\\build\\example.pdb");
+        assert_eq!(codeview.file_name(), b"example.pdb");
+        assert_eq!(codeview.symbol_key(), "123456789ABCDEF001020304050607083");
+        assert_eq!(view.nt_headers().symbol_key(), "000000004000");
+        assert_eq!(view.codeview().unwrap(), Some(codeview));
+
+        assert_eq!(view.debug_codeview(&entries[1]).unwrap(), nt::CodeView::Pdb20 {
+            signature: 0x3a1b2c3d,
+            age: 7,
+            path: CString::new("old.pdb").unwrap(),
+        });
+        assert_eq!(view.debug_codeview(&entries[1]).unwrap().symbol_key(), "3A1B2C3D7");
+        assert!(parse_codeview(b"RSDS").is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use std::io::{self, Read};
-use {image, nt, parse, traits};
+use std::cmp;
+use {cert, image, nt, parse, traits};
 
 pub struct File<R> {
     read: R,
@@ -25,6 +26,41 @@ impl<R: io::Read> File<R> {
     }
 }
 
+impl<R: io::Read + io::Seek> File<R> {
+    pub fn map_image(&mut self) -> io::Result<Vec<u8>> {
+        let mut image = vec![0u8; self.nt.size_of_image() as usize];
+
+        {
+            let mut headers = io::Cursor::new(&mut image[..]);
+            try!(parse::write_headers(self, &mut headers));
+        }
+
+        for section in &self.sections {
+            let size = parse::section_data_len(section) as usize;
+            let start = section.virtual_address() as usize;
+            let end = try!(start.checked_add(size)
+                .and_then(|end| if end <= image.len() { Some(end) } else { None })
+                .ok_or_else(|| parse::invalid_data("section outside of image"))
+            );
+
+            if size > 0 {
+                try!(self.read.seek(io::SeekFrom::Start(section.pointer_to_raw_data() as u64)));
+                try!(self.read.read_exact(&mut image[start..end]));
+            }
+        }
+
+        Ok(image)
+    }
+
+    pub fn certificates(&mut self) -> io::Result<cert::CertificateIterator<&mut R>> {
+        let data = *try!(traits::PeHeaders::directory_header(self, nt::DirectoryEntry::Security)
+            .ok_or_else(|| parse::invalid_data("security segment not found"))
+        );
+
+        Ok(cert::CertificateIterator::new(&mut self.read, data.virtual_address() as u64, data.size() as u64))
+    }
+}
+
 impl<R> traits::PeHeaders for File<R> {
     fn kind(&self) -> nt::NtKind {
         self.nt.kind()
@@ -71,3 +107,21 @@ impl<R> traits::PeHeaders for File<R> {
         }
     }
 }*/
+
+// Reads an image straight from its file bytes, so nothing SizeOfImage large has to be allocated.
+// Sections read as they would be mapped, with zeroes past their raw data.
+impl<'a> traits::PeRead for File<io::Cursor<&'a [u8]>> {
+    type Read = io::Chain<&'a [u8], io::Take<io::Repeat>>;
+
+    fn section_segment(&self, section: &image::SectionHeader, offset: u32, size: u32) -> io::Result<Self::Read> {
+        let data = *self.read.get_ref();
+        let raw = parse::section_data_len(section);
+        let available = if offset < raw { cmp::min(size, raw - offset) } else { 0 };
+
+        let start = section.pointer_to_raw_data() as usize + offset as usize;
+        let segment = try!(data.get(start..start + available as usize)
+            .ok_or_else(|| parse::invalid_data("section data beyond end of file"))
+        );
+        Ok(segment.chain(io::repeat(0).take((size - available) as u64)))
+    }
+}
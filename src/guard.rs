@@ -0,0 +1,124 @@
+use std::io::{self, Read};
+use std::mem::size_of;
+use result::OptionResultExt;
+use {nt, image, parse, traits};
+
+pub struct GuardTableIterator<R> {
+    fuse: bool,
+    count: u64,
+    stride: u32,
+    read: Option<R>,
+}
+
+impl<R> GuardTableIterator<R> {
+    pub fn new(entries: Option<R>, count: u64, stride: u32) -> Self {
+        GuardTableIterator {
+            fuse: false,
+            count: count,
+            stride: stride,
+            read: entries,
+        }
+    }
+}
+
+impl<R: Read> GuardTableIterator<R> {
+    fn try_next(&mut self) -> io::Result<Option<nt::GuardEntry>> {
+        let read = match self.read {
+            Some(ref mut read) if self.count > 0 => read,
+            _ => return Ok(None),
+        };
+
+        let address = try!(parse::read_u32(&mut *read));
+        let mut metadata = [0u8; 0xf];
+        let metadata = &mut metadata[..self.stride as usize - size_of::<u32>()];
+        try!(read.read_exact(metadata));
+        self.count -= 1;
+
+        Ok(Some(nt::GuardEntry {
+            address: address,
+            flags: metadata.first().cloned().unwrap_or(0),
+        }))
+    }
+}
+
+impl<R: Read> Iterator for GuardTableIterator<R> {
+    type Item = io::Result<nt::GuardEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+// GuardFlags carries the number of metadata bytes following each RVA in every guard table
+pub fn guard_table_stride(guard_flags: u32) -> u32 {
+    size_of::<u32>() as u32 + ((guard_flags & image::GUARD_CF_FUNCTION_TABLE_SIZE_MASK) >> image::GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT)
+}
+
+pub fn guard_table<T: traits::PeRead + ?Sized>(view: &T, config: &nt::LoadConfig, table: Option<u64>, count: Option<u64>) -> io::Result<GuardTableIterator<T::Read>> {
+    let stride = guard_table_stride(config.guard_flags().unwrap_or(0));
+    let (table, count) = match (table, count) {
+        (Some(table), Some(count)) if table != 0 && count != 0 => (table, count),
+        _ => return Ok(GuardTableIterator::new(None, 0, stride)),
+    };
+
+    let size = try!(count.checked_mul(stride as u64)
+        .and_then(|size| if size <= u32::max_value() as u64 { Some(size as u32) } else { None })
+        .ok_or_else(|| parse::invalid_data("bad guard table count"))
+    );
+    let read = try!(parse::va_to_rva(view, table).and_then(|rva| view.segment(rva, size)));
+
+    Ok(GuardTableIterator::new(Some(read), count, stride))
+}
+
+#[cfg(test)]
+mod tests {
+    use testing::{ImageBuilder, put_u32};
+    use traits::PeRead;
+    use {nt, image, View};
+
+    #[test]
+    fn function_table() {
+        let mut config = vec![0u8; 0x78];
+        put_u32(&mut config, 0, 0x78);
+        put_u32(&mut config, 0x50, 0x10003000);
+        put_u32(&mut config, 0x54, 3);
+        put_u32(&mut config, 0x58, image::GUARD_CF_INSTRUMENTED | image::GUARD_CF_FUNCTION_TABLE_PRESENT | 1 << image::GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT);
+        put_u32(&mut config, 0x70, 0x10003010);
+        put_u32(&mut config, 0x74, 1);
+
+        let mut tables = vec![0u8; 0x20];
+        put_u32(&mut tables, 0, 0x1000);
+        put_u32(&mut tables, 5, 0x1010);
+        tables[9] = image::GUARD_FLAG_FID_SUPPRESSED;
+        put_u32(&mut tables, 10, 0x1020);
+        tables[14] = image::GUARD_FLAG_EXPORT_SUPPRESSED;
+        put_u32(&mut tables, 0x10, 0x1030);
+
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::LoadConfig, 0x2000, 0x78)
+            .section(0x2000, config)
+            .section(0x3000, tables)
+            .build();
+        let view = View::new(&image[..]).unwrap();
+
+        let functions = view.guard_cf_functions().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(functions, vec![
+            nt::GuardEntry { address: 0x1000, flags: 0 },
+            nt::GuardEntry { address: 0x1010, flags: image::GUARD_FLAG_FID_SUPPRESSED },
+            nt::GuardEntry { address: 0x1020, flags: image::GUARD_FLAG_EXPORT_SUPPRESSED },
+        ]);
+        assert!(functions[1].suppressed() && functions[2].export_suppressed());
+
+        let targets = view.guard_long_jump_targets().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(targets, vec![nt::GuardEntry { address: 0x1030, flags: 0 }]);
+        assert_eq!(view.guard_iat_entries().unwrap().count(), 0);
+    }
+}
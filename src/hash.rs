@@ -0,0 +1,107 @@
+use std::io;
+use md5;
+use {nt, ordinals, traits};
+
+fn digest(entries: &[Vec<u8>]) -> String {
+    format!("{:x}", md5::compute(entries.join(&b","[..])))
+}
+
+fn symbol_name(module: &[u8], symbol: &nt::ImportSymbol) -> Vec<u8> {
+    match *symbol {
+        nt::ImportSymbol::Name { ref name, .. } => name.as_bytes().to_vec(),
+        nt::ImportSymbol::Ordinal(ordinal) => match ordinals::ordinal_name(module, ordinal) {
+            Some(name) => name.as_bytes().to_vec(),
+            None => format!("ord{}", ordinal).into_bytes(),
+        },
+    }
+}
+
+// Matches pefile's get_imphash(): "module.function" pairs in import order, lowercased,
+// with the module extension dropped and known ordinals replaced by their names.
+pub fn imphash<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<Option<String>> {
+    if view.directory_header(nt::DirectoryEntry::Import).is_none() {
+        return Ok(None)
+    }
+
+    let mut entries = Vec::new();
+    for descriptor in try!(view.imports()) {
+        let descriptor = try!(descriptor);
+        let module = try!(view.read_cstring(descriptor.name()));
+        let module = module.as_bytes();
+        let library = match module.iter().rposition(|&c| c == b'.') {
+            Some(dot) if [&b"ocx"[..], b"sys", b"dll"].iter().any(|ext| module[dot + 1..].eq_ignore_ascii_case(ext)) => &module[..dot],
+            _ => module,
+        };
+
+        for thunk in try!(view.import_table(&descriptor)) {
+            let thunk = try!(thunk);
+            let mut entry = library.to_vec();
+            entry.push(b'.');
+            entry.extend(symbol_name(module, &thunk.symbol));
+            entry.make_ascii_lowercase();
+            entries.push(entry);
+        }
+    }
+
+    Ok(Some(digest(&entries)))
+}
+
+// The export counterpart hashes lowercased export names in ordinal order, with "ordN"
+// standing in for exports that only have an ordinal.
+pub fn exphash<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<Option<String>> {
+    if view.directory_header(nt::DirectoryEntry::Export).is_none() {
+        return Ok(None)
+    }
+
+    let mut entries = Vec::new();
+    for export in try!(view.exports()) {
+        let export = try!(export);
+        let mut entry = match export.name {
+            Some(name) => name.into_bytes(),
+            None => format!("ord{}", export.ordinal).into_bytes(),
+        };
+        entry.make_ascii_lowercase();
+        entries.push(entry);
+    }
+
+    Ok(Some(digest(&entries)))
+}
+
+#[cfg(test)]
+mod tests {
+    use testing::{ImageBuilder, ExportTarget, export_directory, put_u32};
+    use traits::PeRead;
+    use {nt, View};
+
+    #[test]
+    fn imphash() {
+        let mut imports = vec![0u8; 0x200];
+        for (i, &value) in [0x2100, 0, 0, 0x2080, 0x2100, 0x2140, 0, 0, 0x2090, 0x2140].iter().enumerate() {
+            put_u32(&mut imports, i * 4, value);
+        }
+        imports[0x80..0x8b].copy_from_slice(b"WS2_32.dll\0");
+        imports[0x90..0x9d].copy_from_slice(b"KERNEL32.dll\0");
+        put_u32(&mut imports, 0x100, 0x80000073);
+        put_u32(&mut imports, 0x104, 0x800003e8);
+        put_u32(&mut imports, 0x140, 0x2180);
+        imports[0x180..0x191].copy_from_slice(b"\0\0GetProcAddress\0");
+
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::Import, 0x2000, 0x3c)
+            .section(0x2000, imports)
+            .build();
+        let view = View::new(&image[..]).unwrap();
+        assert_eq!(view.imphash().unwrap(), Some("9be06d4183d8fc65dfbbeca076ad5671".to_owned()));
+        assert_eq!(view.exphash().unwrap(), None);
+    }
+
+    #[test]
+    fn exphash() {
+        let exports = export_directory(0x1000, "a.dll", &[("Foo", ExportTarget::Rva(0x2000)), ("", ExportTarget::Rva(0x2010)), ("Bar", ExportTarget::Rva(0x2020))]);
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::Export, 0x1000, exports.len() as u32)
+            .section(0x1000, exports)
+            .build();
+        assert_eq!(View::new(&image[..]).unwrap().exphash().unwrap(), Some("753d86a22e366154c45fd978428b0699".to_owned()));
+    }
+}
@@ -4,17 +4,42 @@
 extern crate pod;
 extern crate byteorder_pod;
 extern crate result;
+extern crate md5;
 
 pub extern crate winpe_image as image;
 
 mod traits;
 mod nt;
 mod parse;
+mod resource;
+mod version;
+mod debug;
+mod symstore;
+mod unwind;
+mod arm;
+mod guard;
+mod seh;
+mod bind;
+mod ordinals;
+mod hash;
+mod cert;
 mod view;
 mod file;
 
+#[cfg(test)]
+mod testing;
+
 pub use traits::{PeHeaders, PeRead, PeWrite};
-pub use parse::{write_pe, RelocationIterator, ImportIterator, ImportTableIterator};
-pub use nt::{NtKind, NtHeaders, DirectoryEntry, ImportSymbol, Relocation, RelocationKind};
+pub use parse::{write_pe, resolve_export, RelocationIterator, ImportIterator, ImportTableIterator, DelayImportIterator, ExportIterator};
+pub use nt::{NtKind, NtHeaders, DirectoryEntry, ImportSymbol, ImportThunk, BoundImport, BoundForwarderRef, StaleBinding, Export, ExportAddress, Forwarder, ForwarderSymbol, ResolvedExport, ResourceId, Resource, VersionInfo, VersionStringTable, VersionTranslation, CodeView, Certificate, Tls, LoadConfig, GuardEntry, SafeSeh, UnwindOp, UnwindCode, UnwindHandler, UnwindInfo, FunctionEntry, FunctionUnwind, Arm64Unwind, ArmUnwind, Arm64PackedUnwind, ArmPackedUnwind, ArmXdata, ArmEpilogs, ArmEpilogScope, ArmUnwindCode, Arm64UnwindOp, ArmUnwindOp, Register, Context, Relocation, RelocationKind};
+pub use resource::{ResourceIterator, ResourceTable, ResourceData, write_resources};
+pub use version::parse_version_info;
+pub use debug::{DebugIterator, parse_codeview};
+pub use symstore::{symbol_path, find_symbol};
+pub use unwind::{RuntimeFunctionIterator, FunctionEntryIterator};
+pub use guard::GuardTableIterator;
+pub use bind::{BoundImportIterator, stale_bindings, bind_imports};
+pub use ordinals::{ordinal_name, OrdinalResolver};
+pub use cert::{CertificateIterator, certificates};
 pub use view::View;
 pub use file::File;
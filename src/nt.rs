@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 use std::mem::{transmute, size_of};
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use image::{self, NtHeaders32, NtHeaders64};
 
 #[repr(usize)]
@@ -32,6 +32,619 @@ pub enum ImportSymbol {
     },
 }
 
+// int_rva is None when the descriptor has no name table and the symbol came from the IAT,
+// and iat_value is whatever the slot holds now: the name table entry again, or a bound address
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImportThunk {
+    pub symbol: ImportSymbol,
+    pub int_rva: Option<u32>,
+    pub iat_rva: u32,
+    pub iat_value: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoundImport {
+    pub time_date_stamp: u32,
+    pub module: CString,
+    pub forwarder_refs: Vec<BoundForwarderRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoundForwarderRef {
+    pub time_date_stamp: u32,
+    pub module: CString,
+}
+
+// actual is None when no candidate module matched the bound name
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StaleBinding {
+    pub module: CString,
+    pub bound: u32,
+    pub actual: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Export {
+    pub ordinal: u16,
+    pub name: Option<CString>,
+    pub address: ExportAddress,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ExportAddress {
+    Rva(u32),
+    Forwarder(Forwarder),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Forwarder {
+    pub module: CString,
+    pub symbol: ForwarderSymbol,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ForwarderSymbol {
+    Ordinal(u16),
+    Name(CString),
+}
+
+impl From<ForwarderSymbol> for ImportSymbol {
+    fn from(symbol: ForwarderSymbol) -> Self {
+        match symbol {
+            ForwarderSymbol::Ordinal(ordinal) => ImportSymbol::Ordinal(ordinal),
+            ForwarderSymbol::Name(name) => ImportSymbol::Name {
+                ordinal_hint: 0,
+                name: name,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResolvedExport {
+    pub module: usize,
+    pub address: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ResourceId {
+    Name(String),
+    Id(u16),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Resource {
+    pub kind: ResourceId,
+    pub name: ResourceId,
+    pub language: u16,
+    pub data: image::ResourceDataEntry,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VersionInfo {
+    pub fixed: Option<image::FixedFileInfo>,
+    pub strings: Vec<VersionStringTable>,
+    pub translations: Vec<VersionTranslation>,
+}
+
+impl VersionInfo {
+    pub fn string(&self, key: &str) -> Option<&str> {
+        self.strings.iter().filter_map(|table| table.string(key)).next()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VersionStringTable {
+    pub language: u16,
+    pub code_page: u16,
+    pub strings: Vec<(String, String)>,
+}
+
+impl VersionStringTable {
+    pub fn string(&self, key: &str) -> Option<&str> {
+        self.strings.iter()
+            .find(|&&(ref k, _)| k == key)
+            .map(|&(_, ref v)| &v[..])
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VersionTranslation {
+    pub language: u16,
+    pub code_page: u16,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UnwindOp {
+    PushNonvol(u8),
+    AllocLarge(u32),
+    AllocSmall(u32),
+    SetFpreg,
+    SaveNonvol {
+        register: u8,
+        offset: u32,
+    },
+    SaveXmm128 {
+        register: u8,
+        offset: u32,
+    },
+    Epilog(u8),
+    PushMachframe(bool),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnwindCode {
+    pub offset: u8,
+    pub op: UnwindOp,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnwindHandler {
+    pub address: u32,
+    pub data: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnwindInfo {
+    pub version: u8,
+    pub flags: u8,
+    pub size_of_prolog: u8,
+    pub frame_register: u8,
+    // scaled to bytes, unlike the raw 4-bit field
+    pub frame_offset: u32,
+    pub codes: Vec<UnwindCode>,
+    pub handler: Option<UnwindHandler>,
+    pub chained: Option<image::RuntimeFunction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CodeView {
+    Pdb70 {
+        guid: image::Guid,
+        age: u32,
+        path: CString,
+    },
+    Pdb20 {
+        signature: u32,
+        age: u32,
+        path: CString,
+    },
+}
+
+impl CodeView {
+    pub fn age(&self) -> u32 {
+        match *self {
+            CodeView::Pdb70 { age, .. } | CodeView::Pdb20 { age, .. } => age,
+        }
+    }
+
+    pub fn path(&self) -> &CStr {
+        match *self {
+            CodeView::Pdb70 { ref path, .. } | CodeView::Pdb20 { ref path, .. } => path,
+        }
+    }
+
+    pub fn file_name(&self) -> &[u8] {
+        let path = self.path().to_bytes();
+        match path.iter().rposition(|&c| c == b'\\' || c == b'/') {
+            Some(pos) => &path[pos + 1..],
+            None => path,
+        }
+    }
+
+    pub fn symbol_key(&self) -> String {
+        match *self {
+            CodeView::Pdb70 { ref guid, age, .. } => {
+                let d = guid.data4();
+                format!("{:08X}{:04X}{:04X}{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}{:x}",
+                    guid.data1(), guid.data2(), guid.data3(), d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7], age
+                )
+            },
+            CodeView::Pdb20 { signature, age, .. } => format!("{:08X}{:x}", signature, age),
+        }
+    }
+}
+
+// addresses are RVAs, converted from the VAs stored in the directory
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tls {
+    pub raw_data_start: u32,
+    pub raw_data_end: u32,
+    pub address_of_index: u32,
+    pub callbacks: Vec<u32>,
+    pub size_of_zero_fill: u32,
+    pub characteristics: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Certificate {
+    pub revision: u16,
+    pub kind: u16,
+    pub data: Vec<u8>,
+}
+
+impl Certificate {
+    // the DER-encoded PKCS#7 SignedData of an Authenticode signature
+    pub fn signed_data(&self) -> Option<&[u8]> {
+        if self.kind == image::WIN_CERT_TYPE_PKCS_SIGNED_DATA {
+            Some(&self.data)
+        } else {
+            None
+        }
+    }
+}
+
+// The directory grows with every OS release and images declare how much of it they
+// carry in the leading Size field, so everything past that reads as None.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LoadConfig {
+    Win32(image::LoadConfigDirectory32),
+    Win64(image::LoadConfigDirectory64),
+}
+
+fn load_config_field<T, F>(config: &T, field: &F, size: u32) -> bool {
+    let offset = field as *const F as usize - config as *const T as usize;
+    offset + size_of::<F>() <= size as usize
+}
+
+macro_rules! load_config_fields {
+    ($($field:ident: $ty:ty,)*) => {
+        impl LoadConfig {
+            $(
+                #[inline]
+                pub fn $field(&self) -> Option<$ty> {
+                    match *self {
+                        LoadConfig::Win32(ref config) if load_config_field(config, &config.$field, config.size()) => Some(config.$field() as $ty),
+                        LoadConfig::Win64(ref config) if load_config_field(config, &config.$field, config.size()) => Some(config.$field() as $ty),
+                        _ => None,
+                    }
+                }
+            )*
+        }
+    };
+}
+
+load_config_fields! {
+    time_date_stamp: u32,
+    major_version: u16,
+    minor_version: u16,
+    global_flags_clear: u32,
+    global_flags_set: u32,
+    critical_section_default_timeout: u32,
+    de_commit_free_block_threshold: u64,
+    de_commit_total_free_threshold: u64,
+    lock_prefix_table: u64,
+    maximum_allocation_size: u64,
+    virtual_memory_threshold: u64,
+    process_affinity_mask: u64,
+    process_heap_flags: u32,
+    csd_version: u16,
+    dependent_load_flags: u16,
+    edit_list: u64,
+    security_cookie: u64,
+    se_handler_table: u64,
+    se_handler_count: u64,
+    guard_cf_check_function_pointer: u64,
+    guard_cf_dispatch_function_pointer: u64,
+    guard_cf_function_table: u64,
+    guard_cf_function_count: u64,
+    guard_flags: u32,
+    guard_address_taken_iat_entry_table: u64,
+    guard_address_taken_iat_entry_count: u64,
+    guard_long_jump_target_table: u64,
+    guard_long_jump_target_count: u64,
+    dynamic_value_reloc_table: u64,
+    chpe_metadata_pointer: u64,
+    guard_rf_failure_routine: u64,
+    guard_rf_failure_routine_function_pointer: u64,
+    dynamic_value_reloc_table_offset: u32,
+    dynamic_value_reloc_table_section: u16,
+    guard_rf_verify_stack_pointer_function_pointer: u64,
+    hot_patch_table_offset: u32,
+    enclave_configuration_pointer: u64,
+    volatile_metadata_pointer: u64,
+    guard_eh_continuation_table: u64,
+    guard_eh_continuation_count: u64,
+    guard_xfg_check_function_pointer: u64,
+    guard_xfg_dispatch_function_pointer: u64,
+    guard_xfg_table_dispatch_function_pointer: u64,
+    cast_guard_os_determined_failure_mode: u64,
+    guard_memcpy_function_pointer: u64,
+}
+
+impl LoadConfig {
+    #[inline]
+    pub fn size(&self) -> u32 {
+        match *self {
+            LoadConfig::Win32(ref config) => config.size(),
+            LoadConfig::Win64(ref config) => config.size(),
+        }
+    }
+
+    #[inline]
+    pub fn code_integrity(&self) -> Option<&image::LoadConfigCodeIntegrity> {
+        match *self {
+            LoadConfig::Win32(ref config) if load_config_field(config, &config.code_integrity, config.size()) => Some(config.code_integrity()),
+            LoadConfig::Win64(ref config) if load_config_field(config, &config.code_integrity, config.size()) => Some(config.code_integrity()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GuardEntry {
+    pub address: u32,
+    pub flags: u8,
+}
+
+impl GuardEntry {
+    pub fn suppressed(&self) -> bool {
+        self.flags & image::GUARD_FLAG_FID_SUPPRESSED != 0
+    }
+
+    pub fn export_suppressed(&self) -> bool {
+        self.flags & image::GUARD_FLAG_EXPORT_SUPPRESSED != 0
+    }
+}
+
+// handlers are sorted RVAs; None means the image carries no SEHandlerTable at all
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SafeSeh {
+    pub no_seh: bool,
+    pub handlers: Option<Vec<u32>>,
+}
+
+impl SafeSeh {
+    pub fn compliant(&self) -> bool {
+        self.no_seh || self.handlers.is_some()
+    }
+
+    pub fn is_registered(&self, rva: u32) -> bool {
+        match self.handlers {
+            Some(ref handlers) => handlers.binary_search(&rva).is_ok(),
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FunctionEntry {
+    Amd64(image::RuntimeFunction),
+    Arm64(image::ArmRuntimeFunction),
+    Arm(image::ArmRuntimeFunction),
+}
+
+impl FunctionEntry {
+    pub fn begin_address(&self) -> u32 {
+        match *self {
+            FunctionEntry::Amd64(ref function) => function.begin_address(),
+            FunctionEntry::Arm64(ref function) => function.begin_address(),
+            // the low bit only marks the entry point as Thumb code
+            FunctionEntry::Arm(ref function) => function.begin_address() & !1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FunctionUnwind {
+    Amd64(UnwindInfo),
+    Arm64(Arm64Unwind),
+    Arm(ArmUnwind),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Arm64Unwind {
+    Packed(Arm64PackedUnwind),
+    Xdata(ArmXdata<Arm64UnwindOp>),
+}
+
+impl Arm64Unwind {
+    pub fn function_length(&self) -> u32 {
+        match *self {
+            Arm64Unwind::Packed(ref packed) => packed.function_length,
+            Arm64Unwind::Xdata(ref xdata) => xdata.function_length,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ArmUnwind {
+    Packed(ArmPackedUnwind),
+    Xdata(ArmXdata<ArmUnwindOp>),
+}
+
+impl ArmUnwind {
+    pub fn function_length(&self) -> u32 {
+        match *self {
+            ArmUnwind::Packed(ref packed) => packed.function_length,
+            ArmUnwind::Xdata(ref xdata) => xdata.function_length,
+        }
+    }
+}
+
+// lengths and frame sizes are scaled to bytes, but ARM's stack_adjust is left raw as
+// its top values encode prolog and epilog folding instead of a size
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Arm64PackedUnwind {
+    pub fragment: bool,
+    pub function_length: u32,
+    pub reg_f: u8,
+    pub reg_i: u8,
+    pub homed: bool,
+    pub cr: u8,
+    pub frame_size: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArmPackedUnwind {
+    pub fragment: bool,
+    pub function_length: u32,
+    pub ret: u8,
+    pub homed: bool,
+    pub reg: u8,
+    pub r: bool,
+    pub l: bool,
+    pub c: bool,
+    pub stack_adjust: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArmXdata<T> {
+    pub function_length: u32,
+    pub version: u8,
+    pub fragment: bool,
+    pub epilogs: ArmEpilogs,
+    pub codes: Vec<ArmUnwindCode<T>>,
+    pub handler: Option<UnwindHandler>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ArmEpilogs {
+    // a single epilog at the end of the function, starting at this code index
+    Single(u16),
+    Scopes(Vec<ArmEpilogScope>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArmEpilogScope {
+    pub offset: u32,
+    pub condition: u8,
+    pub index: u16,
+}
+
+// index is the byte offset into the code array that epilog scopes refer to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArmUnwindCode<T> {
+    pub index: u16,
+    pub op: T,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Arm64UnwindOp {
+    Alloc(u32),
+    SaveR19R20X(u32),
+    SaveFpLr(u32),
+    SaveFpLrX(u32),
+    SaveRegP {
+        register: u8,
+        offset: u32,
+    },
+    SaveRegPX {
+        register: u8,
+        offset: u32,
+    },
+    SaveReg {
+        register: u8,
+        offset: u32,
+    },
+    SaveRegX {
+        register: u8,
+        offset: u32,
+    },
+    SaveLrPair {
+        register: u8,
+        offset: u32,
+    },
+    SaveFRegP {
+        register: u8,
+        offset: u32,
+    },
+    SaveFRegPX {
+        register: u8,
+        offset: u32,
+    },
+    SaveFReg {
+        register: u8,
+        offset: u32,
+    },
+    SaveFRegX {
+        register: u8,
+        offset: u32,
+    },
+    AllocZ(u8),
+    SetFp,
+    AddFp(u32),
+    Nop,
+    End,
+    EndC,
+    SaveNext,
+    TrapFrame,
+    MachineFrame,
+    Context,
+    EcContext,
+    ClearUnwoundToCall,
+    PacSignLr,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ArmUnwindOp {
+    AddSp {
+        size: u32,
+        wide: bool,
+    },
+    // bits 0-12 are r0-r12 and bit 14 is lr, matching the instruction encoding
+    Pop {
+        registers: u16,
+        wide: bool,
+    },
+    MovSp(u8),
+    VPop {
+        first: u8,
+        last: u8,
+    },
+    LdrLr(u32),
+    MicrosoftSpecific(u8),
+    Nop {
+        wide: bool,
+    },
+    End,
+    EndNop {
+        wide: bool,
+    },
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Register {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsp = 4,
+    Rbp = 5,
+    Rsi = 6,
+    Rdi = 7,
+    R8 = 8,
+    R9 = 9,
+    R10 = 10,
+    R11 = 11,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Context {
+    pub rip: u64,
+    pub registers: [u64; 16],
+    pub xmm: [[u64; 2]; 16],
+}
+
+impl Context {
+    pub fn register(&self, register: Register) -> u64 {
+        self.registers[register as usize]
+    }
+
+    pub fn set_register(&mut self, register: Register, value: u64) {
+        self.registers[register as usize] = value
+    }
+
+    pub fn rsp(&self) -> u64 {
+        self.register(Register::Rsp)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Relocation {
     pub kind: RelocationKind,
@@ -69,6 +682,14 @@ pub enum NtKind {
 }
 
 impl NtKind {
+    #[inline]
+    pub fn size_of_va(&self) -> u32 {
+        match *self {
+            NtKind::Win32 => size_of::<u32>() as u32,
+            NtKind::Win64 => size_of::<u64>() as u32,
+        }
+    }
+
     #[inline]
     pub fn size_of_optional_header(&self) -> usize {
         match *self {
@@ -365,6 +986,10 @@ impl<'a> NtHeaders<'a> {
         }
     }
 
+    pub fn symbol_key(&self) -> String {
+        format!("{:08X}{:x}", self.file_header().time_date_stamp(), self.size_of_image())
+    }
+
     #[inline]
     pub fn image_base(&self) -> u64 {
         match *self {
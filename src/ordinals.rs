@@ -0,0 +1,531 @@
+use std::io::{self, BufRead};
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::path::Path;
+use std::fs;
+use {nt, parse, traits, File};
+
+// Names for the ordinal-only exports of system DLLs whose ordinals have stayed put across
+// Windows releases, the same ws2_32/wsock32 and oleaut32 set pefile ships. Lookups are keyed by
+// the full lowercase module name the way pefile does it, so that imphash values agree with other
+// tools. There are no MFC tables here; names for MFC imports can be learned from the .def of the
+// MFC DLL in question through OrdinalResolver::learn_def.
+
+static WS2_32: &'static [(u16, &'static str)] = &[
+    (1, "accept"),
+    (2, "bind"),
+    (3, "closesocket"),
+    (4, "connect"),
+    (5, "getpeername"),
+    (6, "getsockname"),
+    (7, "getsockopt"),
+    (8, "htonl"),
+    (9, "htons"),
+    (10, "ioctlsocket"),
+    (11, "inet_addr"),
+    (12, "inet_ntoa"),
+    (13, "listen"),
+    (14, "ntohl"),
+    (15, "ntohs"),
+    (16, "recv"),
+    (17, "recvfrom"),
+    (18, "select"),
+    (19, "send"),
+    (20, "sendto"),
+    (21, "setsockopt"),
+    (22, "shutdown"),
+    (23, "socket"),
+    (24, "GetAddrInfoW"),
+    (25, "GetNameInfoW"),
+    (26, "WSApSetPostRoutine"),
+    (27, "FreeAddrInfoW"),
+    (28, "WPUCompleteOverlappedRequest"),
+    (29, "WSAAccept"),
+    (30, "WSAAddressToStringA"),
+    (31, "WSAAddressToStringW"),
+    (32, "WSACloseEvent"),
+    (33, "WSAConnect"),
+    (34, "WSACreateEvent"),
+    (35, "WSADuplicateSocketA"),
+    (36, "WSADuplicateSocketW"),
+    (37, "WSAEnumNameSpaceProvidersA"),
+    (38, "WSAEnumNameSpaceProvidersW"),
+    (39, "WSAEnumNetworkEvents"),
+    (40, "WSAEnumProtocolsA"),
+    (41, "WSAEnumProtocolsW"),
+    (42, "WSAEventSelect"),
+    (43, "WSAGetOverlappedResult"),
+    (44, "WSAGetQOSByName"),
+    (45, "WSAGetServiceClassInfoA"),
+    (46, "WSAGetServiceClassInfoW"),
+    (47, "WSAGetServiceClassNameByClassIdA"),
+    (48, "WSAGetServiceClassNameByClassIdW"),
+    (49, "WSAHtonl"),
+    (50, "WSAHtons"),
+    (51, "gethostbyaddr"),
+    (52, "gethostbyname"),
+    (53, "getprotobyname"),
+    (54, "getprotobynumber"),
+    (55, "getservbyname"),
+    (56, "getservbyport"),
+    (57, "gethostname"),
+    (58, "WSAInstallServiceClassA"),
+    (59, "WSAInstallServiceClassW"),
+    (60, "WSAIoctl"),
+    (61, "WSAJoinLeaf"),
+    (62, "WSALookupServiceBeginA"),
+    (63, "WSALookupServiceBeginW"),
+    (64, "WSALookupServiceEnd"),
+    (65, "WSALookupServiceNextA"),
+    (66, "WSALookupServiceNextW"),
+    (67, "WSANSPIoctl"),
+    (68, "WSANtohl"),
+    (69, "WSANtohs"),
+    (70, "WSAProviderConfigChange"),
+    (71, "WSARecv"),
+    (72, "WSARecvDisconnect"),
+    (73, "WSARecvFrom"),
+    (74, "WSARemoveServiceClass"),
+    (75, "WSAResetEvent"),
+    (76, "WSASend"),
+    (77, "WSASendDisconnect"),
+    (78, "WSASendTo"),
+    (79, "WSASetEvent"),
+    (80, "WSASetServiceA"),
+    (81, "WSASetServiceW"),
+    (82, "WSASocketA"),
+    (83, "WSASocketW"),
+    (84, "WSAStringToAddressA"),
+    (85, "WSAStringToAddressW"),
+    (86, "WSAWaitForMultipleEvents"),
+    (87, "WSCDeinstallProvider"),
+    (88, "WSCEnableNSProvider"),
+    (89, "WSCEnumProtocols"),
+    (90, "WSCGetProviderPath"),
+    (91, "WSCInstallNameSpace"),
+    (92, "WSCInstallProvider"),
+    (93, "WSCUnInstallNameSpace"),
+    (94, "WSCUpdateProvider"),
+    (95, "WSCWriteNameSpaceOrder"),
+    (96, "WSCWriteProviderOrder"),
+    (97, "freeaddrinfo"),
+    (98, "getaddrinfo"),
+    (99, "getnameinfo"),
+    (101, "WSAAsyncSelect"),
+    (102, "WSAAsyncGetHostByAddr"),
+    (103, "WSAAsyncGetHostByName"),
+    (104, "WSAAsyncGetProtoByNumber"),
+    (105, "WSAAsyncGetProtoByName"),
+    (106, "WSAAsyncGetServByPort"),
+    (107, "WSAAsyncGetServByName"),
+    (108, "WSACancelAsyncRequest"),
+    (109, "WSASetBlockingHook"),
+    (110, "WSAUnhookBlockingHook"),
+    (111, "WSAGetLastError"),
+    (112, "WSASetLastError"),
+    (113, "WSACancelBlockingCall"),
+    (114, "WSAIsBlocking"),
+    (115, "WSAStartup"),
+    (116, "WSACleanup"),
+    (151, "__WSAFDIsSet"),
+    (500, "WEP"),
+];
+
+static OLEAUT32: &'static [(u16, &'static str)] = &[
+    (2, "SysAllocString"),
+    (3, "SysReAllocString"),
+    (4, "SysAllocStringLen"),
+    (5, "SysReAllocStringLen"),
+    (6, "SysFreeString"),
+    (7, "SysStringLen"),
+    (8, "VariantInit"),
+    (9, "VariantClear"),
+    (10, "VariantCopy"),
+    (11, "VariantCopyInd"),
+    (12, "VariantChangeType"),
+    (13, "VariantTimeToDosDateTime"),
+    (14, "DosDateTimeToVariantTime"),
+    (15, "SafeArrayCreate"),
+    (16, "SafeArrayDestroy"),
+    (17, "SafeArrayGetDim"),
+    (18, "SafeArrayGetElemsize"),
+    (19, "SafeArrayGetUBound"),
+    (20, "SafeArrayGetLBound"),
+    (21, "SafeArrayLock"),
+    (22, "SafeArrayUnlock"),
+    (23, "SafeArrayAccessData"),
+    (24, "SafeArrayUnaccessData"),
+    (25, "SafeArrayGetElement"),
+    (26, "SafeArrayPutElement"),
+    (27, "SafeArrayCopy"),
+    (28, "DispGetParam"),
+    (29, "DispGetIDsOfNames"),
+    (30, "DispInvoke"),
+    (31, "CreateDispTypeInfo"),
+    (32, "CreateStdDispatch"),
+    (33, "RegisterActiveObject"),
+    (34, "RevokeActiveObject"),
+    (35, "GetActiveObject"),
+    (36, "SafeArrayAllocDescriptor"),
+    (37, "SafeArrayAllocData"),
+    (38, "SafeArrayDestroyDescriptor"),
+    (39, "SafeArrayDestroyData"),
+    (40, "SafeArrayRedim"),
+    (41, "SafeArrayAllocDescriptorEx"),
+    (42, "SafeArrayCreateEx"),
+    (43, "SafeArrayCreateVectorEx"),
+    (44, "SafeArraySetRecordInfo"),
+    (45, "SafeArrayGetRecordInfo"),
+    (46, "VarParseNumFromStr"),
+    (47, "VarNumFromParseNum"),
+    (48, "VarI2FromUI1"),
+    (49, "VarI2FromI4"),
+    (50, "VarI2FromR4"),
+    (51, "VarI2FromR8"),
+    (52, "VarI2FromCy"),
+    (53, "VarI2FromDate"),
+    (54, "VarI2FromStr"),
+    (55, "VarI2FromDisp"),
+    (56, "VarI2FromBool"),
+    (57, "SafeArraySetIID"),
+    (58, "VarI4FromUI1"),
+    (59, "VarI4FromI2"),
+    (60, "VarI4FromR4"),
+    (61, "VarI4FromR8"),
+    (62, "VarI4FromCy"),
+    (63, "VarI4FromDate"),
+    (64, "VarI4FromStr"),
+    (65, "VarI4FromDisp"),
+    (66, "VarI4FromBool"),
+    (67, "SafeArrayGetIID"),
+    (68, "VarR4FromUI1"),
+    (69, "VarR4FromI2"),
+    (70, "VarR4FromI4"),
+    (71, "VarR4FromR8"),
+    (72, "VarR4FromCy"),
+    (73, "VarR4FromDate"),
+    (74, "VarR4FromStr"),
+    (75, "VarR4FromDisp"),
+    (76, "VarR4FromBool"),
+    (77, "SafeArrayGetVartype"),
+    (78, "VarR8FromUI1"),
+    (79, "VarR8FromI2"),
+    (80, "VarR8FromI4"),
+    (81, "VarR8FromR4"),
+    (82, "VarR8FromCy"),
+    (83, "VarR8FromDate"),
+    (84, "VarR8FromStr"),
+    (85, "VarR8FromDisp"),
+    (86, "VarR8FromBool"),
+    (87, "VarFormat"),
+    (88, "VarDateFromUI1"),
+    (89, "VarDateFromI2"),
+    (90, "VarDateFromI4"),
+    (91, "VarDateFromR4"),
+    (92, "VarDateFromR8"),
+    (93, "VarDateFromCy"),
+    (94, "VarDateFromStr"),
+    (95, "VarDateFromDisp"),
+    (96, "VarDateFromBool"),
+    (97, "VarFormatDateTime"),
+    (98, "VarCyFromUI1"),
+    (99, "VarCyFromI2"),
+    (100, "VarCyFromI4"),
+    (101, "VarCyFromR4"),
+    (102, "VarCyFromR8"),
+    (103, "VarCyFromDate"),
+    (104, "VarCyFromStr"),
+    (105, "VarCyFromDisp"),
+    (106, "VarCyFromBool"),
+    (107, "VarFormatNumber"),
+    (108, "VarBstrFromUI1"),
+    (109, "VarBstrFromI2"),
+    (110, "VarBstrFromI4"),
+    (111, "VarBstrFromR4"),
+    (112, "VarBstrFromR8"),
+    (113, "VarBstrFromCy"),
+    (114, "VarBstrFromDate"),
+    (115, "VarBstrFromDisp"),
+    (116, "VarBstrFromBool"),
+    (117, "VarFormatPercent"),
+    (118, "VarBoolFromUI1"),
+    (119, "VarBoolFromI2"),
+    (120, "VarBoolFromI4"),
+    (121, "VarBoolFromR4"),
+    (122, "VarBoolFromR8"),
+    (123, "VarBoolFromDate"),
+    (124, "VarBoolFromCy"),
+    (125, "VarBoolFromStr"),
+    (126, "VarBoolFromDisp"),
+    (127, "VarFormatCurrency"),
+    (128, "VarWeekdayName"),
+    (129, "VarMonthName"),
+    (130, "VarUI1FromI2"),
+    (131, "VarUI1FromI4"),
+    (132, "VarUI1FromR4"),
+    (133, "VarUI1FromR8"),
+    (134, "VarUI1FromCy"),
+    (135, "VarUI1FromDate"),
+    (136, "VarUI1FromStr"),
+    (137, "VarUI1FromDisp"),
+    (138, "VarUI1FromBool"),
+    (139, "VarFormatFromTokens"),
+    (140, "VarTokenizeFormatString"),
+    (141, "VarAdd"),
+    (142, "VarAnd"),
+    (143, "VarDiv"),
+    (144, "DllCanUnloadNow"),
+    (145, "DllGetClassObject"),
+    (146, "DispCallFunc"),
+    (147, "VariantChangeTypeEx"),
+    (148, "SafeArrayPtrOfIndex"),
+    (149, "SysStringByteLen"),
+    (150, "SysAllocStringByteLen"),
+    (151, "DllRegisterServer"),
+    (152, "VarEqv"),
+    (153, "VarIdiv"),
+    (154, "VarImp"),
+    (155, "VarMod"),
+    (156, "VarMul"),
+    (157, "VarOr"),
+    (158, "VarPow"),
+    (159, "VarSub"),
+    (160, "CreateTypeLib"),
+    (161, "LoadTypeLib"),
+    (162, "LoadRegTypeLib"),
+    (163, "RegisterTypeLib"),
+    (164, "QueryPathOfRegTypeLib"),
+    (165, "LHashValOfNameSys"),
+    (166, "LHashValOfNameSysA"),
+    (167, "VarXor"),
+    (168, "VarAbs"),
+    (169, "VarFix"),
+    (170, "OaBuildVersion"),
+    (171, "ClearCustData"),
+    (172, "VarInt"),
+    (173, "VarNeg"),
+    (174, "VarNot"),
+    (175, "VarRound"),
+    (176, "VarCmp"),
+    (177, "VarDecAdd"),
+    (178, "VarDecDiv"),
+    (179, "VarDecMul"),
+    (180, "CreateTypeLib2"),
+    (181, "VarDecSub"),
+    (182, "VarDecAbs"),
+    (183, "LoadTypeLibEx"),
+    (184, "SystemTimeToVariantTime"),
+    (185, "VariantTimeToSystemTime"),
+    (186, "UnRegisterTypeLib"),
+    (200, "GetErrorInfo"),
+    (201, "SetErrorInfo"),
+    (202, "CreateErrorInfo"),
+];
+
+static DEF_STATEMENTS: &'static [&'static str] = &[
+    "NAME", "LIBRARY", "DESCRIPTION", "STACKSIZE", "HEAPSIZE", "SECTIONS", "VERSION", "STUB", "CODE", "DATA", "SEGMENTS", "IMPORTS",
+];
+
+fn table(module: &[u8]) -> Option<&'static [(u16, &'static str)]> {
+    let module = module.to_ascii_lowercase();
+    match &module[..] {
+        b"ws2_32.dll" | b"wsock32.dll" => Some(WS2_32),
+        b"oleaut32.dll" => Some(OLEAUT32),
+        _ => None,
+    }
+}
+
+pub fn ordinal_name(module: &[u8], ordinal: u16) -> Option<&'static str> {
+    table(module).and_then(|table| table.binary_search_by_key(&ordinal, |&(ordinal, _)| ordinal).ok()
+        .map(|index| table[index].1)
+    )
+}
+
+// Learned names take precedence over the builtin tables. MFC and similar DLLs export by
+// ordinal only, so their names have to come from the .def files they were linked with.
+#[derive(Debug, Clone, Default)]
+pub struct OrdinalResolver {
+    modules: Vec<(Vec<u8>, BTreeMap<u16, CString>)>,
+}
+
+impl OrdinalResolver {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn module_mut(&mut self, module: &[u8]) -> &mut BTreeMap<u16, CString> {
+        let index = match self.modules.iter().position(|&(ref name, _)| parse::module_name_eq(name, module)) {
+            Some(index) => index,
+            None => {
+                self.modules.push((module.to_vec(), BTreeMap::new()));
+                self.modules.len() - 1
+            },
+        };
+
+        &mut self.modules[index].1
+    }
+
+    pub fn insert(&mut self, module: &[u8], ordinal: u16, name: CString) {
+        self.module_mut(module).insert(ordinal, name);
+    }
+
+    pub fn lookup(&self, module: &[u8], ordinal: u16) -> Option<&[u8]> {
+        let learned = self.modules.iter()
+            .find(|&&(ref name, _)| parse::module_name_eq(name, module))
+            .and_then(|&(_, ref names)| names.get(&ordinal));
+
+        match learned {
+            Some(name) => Some(name.as_bytes()),
+            None if module.contains(&b'.') => ordinal_name(module, ordinal).map(str::as_bytes),
+            None => ordinal_name(&[module, b".dll"].concat(), ordinal).map(str::as_bytes),
+        }
+    }
+
+    // named imports keep their name, and unknown ordinals become "ordN" as in pefile
+    pub fn name(&self, module: &[u8], symbol: &nt::ImportSymbol) -> CString {
+        match *symbol {
+            nt::ImportSymbol::Name { ref name, .. } => name.clone(),
+            nt::ImportSymbol::Ordinal(ordinal) => match self.lookup(module, ordinal) {
+                Some(name) => CString::new(name).unwrap(),
+                None => CString::new(format!("ord{}", ordinal)).unwrap(),
+            },
+        }
+    }
+
+    pub fn learn<T: traits::PeRead>(&mut self, view: &T) -> io::Result<usize> {
+        let directory = try!(view.export_directory());
+        let module = try!(view.read_cstring(directory.name()));
+
+        let mut count = 0;
+        for export in try!(view.exports()) {
+            let export = try!(export);
+            if let Some(name) = export.name {
+                self.insert(module.as_bytes(), export.ordinal, name);
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    // files that aren't PE images or have no exports are skipped
+    pub fn learn_directory<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        let mut count = 0;
+        for entry in try!(fs::read_dir(path)) {
+            let path = try!(entry).path();
+            let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+            match extension.as_ref().map(|e| &e[..]) {
+                Some("dll") | Some("ocx") | Some("drv") | Some("cpl") => (),
+                _ => continue,
+            }
+
+            // read from the file as is, since mapping would trust SizeOfImage for the allocation
+            let data = try!(fs::read(&path));
+            match File::new(io::Cursor::new(&data[..])).and_then(|file| self.learn(&file)) {
+                Ok(learned) => count += learned,
+                Err(ref e) if e.kind() == io::ErrorKind::InvalidData || e.kind() == io::ErrorKind::UnexpectedEof => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(count)
+    }
+
+    // reads "name[=internal] @ordinal [NONAME] ..." lines from the EXPORTS section of a .def file
+    pub fn learn_def<R: BufRead>(&mut self, module: &[u8], read: R) -> io::Result<usize> {
+        let mut exports = false;
+        let mut count = 0;
+        for line in read.lines() {
+            let line = try!(line);
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue
+            }
+
+            let mut words = line.split_whitespace();
+            let first = words.next().unwrap_or("");
+            if first.eq_ignore_ascii_case("EXPORTS") {
+                exports = true;
+                continue
+            } else if DEF_STATEMENTS.iter().any(|statement| first.eq_ignore_ascii_case(statement)) {
+                exports = false;
+                continue
+            } else if !exports {
+                continue
+            }
+
+            let name = first.split('=').next().unwrap_or(first);
+            // decorated names contain '@' too, so only look at the words after the name
+            let ordinal = match words.next() {
+                Some("@") => words.next(),
+                Some(word) if word.starts_with('@') => Some(&word[1..]),
+                _ => None,
+            }.and_then(|ordinal| ordinal.parse::<u16>().ok());
+            if let Some(ordinal) = ordinal {
+                let name = try!(CString::new(name).map_err(|_| parse::invalid_data("export name contains a nul")));
+                self.insert(module, ordinal, name);
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+    use std::ffi::CString;
+    use std::io::Write;
+    use testing::{ImageBuilder, ExportTarget, export_directory, put_u32};
+    use {nt, View};
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        assert_eq!(ordinal_name(b"WS2_32.dll", 115), Some("WSAStartup"));
+        assert_eq!(ordinal_name(b"wsock32.dll", 23), Some("socket"));
+        assert_eq!(ordinal_name(b"OLEAUT32.DLL", 6), Some("SysFreeString"));
+        assert_eq!(ordinal_name(b"oleaut32.dll", 1), None);
+        assert_eq!(ordinal_name(b"ws2_32", 115), None);
+        assert_eq!(ordinal_name(b"kernel32.dll", 1), None);
+    }
+
+    #[test]
+    fn resolver() {
+        let exports = export_directory(0x1000, "MYLIB.dll", &[("Open", ExportTarget::Rva(0x1100)), ("", ExportTarget::Rva(0x1110)), ("Close", ExportTarget::Rva(0x1120))]);
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::Export, 0x1000, exports.len() as u32)
+            .section(0x1000, exports)
+            .build();
+
+        let mut resolver = OrdinalResolver::new();
+        assert_eq!(resolver.learn(&View::new(&image[..]).unwrap()).unwrap(), 2);
+        assert_eq!(resolver.lookup(b"mylib", 3), Some(&b"Close"[..]));
+        assert_eq!(resolver.lookup(b"mylib.dll", 2), None);
+
+        let def = "LIBRARY MFC42\nEXPORTS\n    ; comment\n    ??0CWnd@@QAE@XZ @ 567 NONAME\n    AfxGetApp=_AfxGetApp@0 @1575 NONAME\nSECTIONS\n    .data READ WRITE\n";
+        assert_eq!(resolver.learn_def(b"mfc42.dll", def.as_bytes()).unwrap(), 2);
+        assert_eq!(resolver.lookup(b"MFC42.DLL", 567), Some(&b"??0CWnd@@QAE@XZ"[..]));
+        assert_eq!(resolver.name(b"mfc42.dll", &nt::ImportSymbol::Ordinal(1575)), CString::new("AfxGetApp").unwrap());
+
+        assert_eq!(resolver.name(b"WS2_32", &nt::ImportSymbol::Ordinal(115)), CString::new("WSAStartup").unwrap());
+        assert_eq!(resolver.name(b"mfc42.dll", &nt::ImportSymbol::Ordinal(7)), CString::new("ord7").unwrap());
+
+        let root = env::temp_dir().join(format!("winpe-ordinals-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::File::create(root.join("mylib.dll")).unwrap().write_all(&image).unwrap();
+        // a SizeOfImage this large must not be allocated just to read the exports
+        let mut huge = image.clone();
+        put_u32(&mut huge, 0x90, 0xfff00000);
+        fs::File::create(root.join("huge.dll")).unwrap().write_all(&huge).unwrap();
+        fs::File::create(root.join("broken.dll")).unwrap().write_all(b"MZ").unwrap();
+        fs::File::create(root.join("readme.txt")).unwrap().write_all(b"").unwrap();
+
+        let mut learned = OrdinalResolver::new();
+        assert_eq!(learned.learn_directory(&root).unwrap(), 4);
+        assert_eq!(learned.lookup(b"MYLIB.dll", 1), Some(&b"Open"[..]));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
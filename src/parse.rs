@@ -1,7 +1,8 @@
 use std::io::{self, Read};
 use std::mem::size_of;
 use std::borrow::Cow;
-use std::cmp;
+use std::{cmp, str};
+use std::ffi::{CStr, CString};
 use pod::{Pod, PodReadExt, PodWriteExt};
 use byteorder_pod::unaligned::Le;
 use result::OptionResultExt;
@@ -11,6 +12,36 @@ pub fn invalid_data(message: &str) -> io::Error {
     io::Error::new(io::ErrorKind::InvalidData, message)
 }
 
+pub fn align_up(value: u32, alignment: u32) -> Option<u32> {
+    match alignment {
+        0 => Some(value),
+        alignment => value.checked_add(alignment - 1).map(|value| value / alignment * alignment),
+    }
+}
+
+// The optional header CheckSum: a folded 16-bit sum over the file with the field itself
+// counted as zero, plus the file length
+pub fn check_sum(data: &[u8], field: usize) -> u32 {
+    let byte = |offset: usize| match data.get(offset) {
+        Some(_) if offset >= field && offset < field + size_of::<u32>() => 0,
+        Some(&byte) => byte as u32,
+        None => 0,
+    };
+    let sum = (0..(data.len() + 1) / 2).fold(0u32, |sum, i| {
+        let sum = sum + (byte(i * 2) | byte(i * 2 + 1) << 8);
+        (sum & 0xffff) + (sum >> 16)
+    });
+    sum.wrapping_add(data.len() as u32)
+}
+
+pub fn read_u16<R: Read>(mut read: R) -> io::Result<u16> {
+    read.read_pod().map(u16::from_le)
+}
+
+pub fn read_u32<R: Read>(mut read: R) -> io::Result<u32> {
+    read.read_pod().map(u32::from_le)
+}
+
 pub struct RelocationIterator<R> {
     base: u32,
     size: u32,
@@ -120,49 +151,212 @@ impl<R: Read> Iterator for ImportIterator<R> {
     }
 }
 
+// Walks the name table when the descriptor has one, otherwise the address table itself,
+// reporting the matching address table slot alongside each symbol.
 pub struct ImportTableIterator<R, T> {
     fuse: bool,
     read: R,
     view: T,
+    base: u64,
+    names: Option<u32>,
+    addresses: u32,
 }
 
 impl<R, T> ImportTableIterator<R, T> {
-    pub fn new(imports: R, view: T) -> Self {
+    pub fn new(thunks: R, view: T, names: Option<u32>, addresses: u32) -> Self {
+        Self::with_base(thunks, view, names, addresses, 0)
+    }
+
+    // old-style delay import name tables hold VAs rather than RVAs
+    pub fn with_base(thunks: R, view: T, names: Option<u32>, addresses: u32, base: u64) -> Self {
         ImportTableIterator {
             fuse: false,
-            read: imports,
+            read: thunks,
             view: view,
+            base: base,
+            names: names,
+            addresses: addresses,
         }
     }
 }
 
 impl<R: Read, T: traits::PeRead> ImportTableIterator<R, T> {
-    fn try_next(&mut self) -> io::Result<Option<nt::ImportSymbol>> {
+    fn try_next(&mut self) -> io::Result<Option<nt::ImportThunk>> {
         let value = try!(match self.view.kind() {
             nt::NtKind::Win32 => self.read.read_pod::<image::ThunkData32>().map(From::from),
             nt::NtKind::Win64 => self.read.read_pod::<image::ThunkData64>(),
         });
 
-        Ok(if value.address_of_data() == 0 {
-            None
+        let symbol = if value.address_of_data() == 0 {
+            return Ok(None)
         } else if value.is_ordinal() {
-            Some(nt::ImportSymbol::Ordinal(value.ordinal16()))
+            nt::ImportSymbol::Ordinal(value.ordinal16())
         } else {
+            let address = try!(value.address_of_data().checked_sub(self.base)
+                .ok_or_else(|| invalid_data("virtual address outside of image"))
+            ) as u32;
             let data: image::ImportByName = try!(
-                self.view.segment(value.address_of_data() as u32, size_of::<image::ImportByName>() as _)
+                self.view.segment(address, size_of::<image::ImportByName>() as _)
                 .and_then(|mut r| r.read_pod())
             );
 
-            Some(nt::ImportSymbol::Name {
+            nt::ImportSymbol::Name {
                 ordinal_hint: data.hint(),
-                name: try!(self.view.read_cstring(value.address_of_data() as u32 + size_of::<image::ImportByName>() as u32)),
-            })
-        })
+                name: try!(self.view.read_cstring(address + size_of::<image::ImportByName>() as u32)),
+            }
+        };
+
+        let size = self.view.kind().size_of_va();
+        let thunk = nt::ImportThunk {
+            symbol: symbol,
+            int_rva: self.names,
+            iat_rva: self.addresses,
+            iat_value: try!(self.view.segment(self.addresses, size).and_then(|read| read_va(self.view.kind(), read))),
+        };
+        self.names = self.names.map(|names| names + size);
+        self.addresses += size;
+
+        Ok(Some(thunk))
     }
 }
 
 impl<R: Read, T: traits::PeRead> Iterator for ImportTableIterator<R, T> {
-    type Item = io::Result<nt::ImportSymbol>;
+    type Item = io::Result<nt::ImportThunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+pub struct DelayImportIterator<R, T> {
+    fuse: bool,
+    read: R,
+    view: T,
+}
+
+impl<R, T> DelayImportIterator<R, T> {
+    pub fn new(imports: R, view: T) -> Self {
+        DelayImportIterator {
+            fuse: false,
+            read: imports,
+            view: view,
+        }
+    }
+}
+
+impl<R: Read, T: traits::PeRead> DelayImportIterator<R, T> {
+    fn try_next(&mut self) -> io::Result<Option<image::DelayLoadDescriptor>> {
+        let mut import = match try!(self.read.read_pod_or_none::<image::DelayLoadDescriptor>()) {
+            Some(import) if import.dll_name_rva() == 0 => return Ok(None),
+            Some(import) => import,
+            None => return Ok(None),
+        };
+
+        // VC6-era descriptors store VAs; yield RVAs either way and leave attributes as found
+        if !import.rva_based() {
+            let view = &self.view;
+            let rva = |field: &mut Le<u32>| -> io::Result<()> {
+                if field.get() != 0 {
+                    field.set(try!(va_to_rva(view, field.get() as u64)));
+                }
+                Ok(())
+            };
+
+            try!(rva(&mut import.dll_name_rva));
+            try!(rva(&mut import.module_handle_rva));
+            try!(rva(&mut import.import_address_table_rva));
+            try!(rva(&mut import.import_name_table_rva));
+            try!(rva(&mut import.bound_import_address_table_rva));
+            try!(rva(&mut import.unload_information_table_rva));
+        }
+
+        Ok(Some(import))
+    }
+}
+
+impl<R: Read, T: traits::PeRead> Iterator for DelayImportIterator<R, T> {
+    type Item = io::Result<image::DelayLoadDescriptor>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+pub struct ExportIterator<R, T> {
+    fuse: bool,
+    index: u32,
+    directory: image::ExportDirectory,
+    data: image::DataDirectory,
+    names: Vec<(u32, u32)>,
+    read: R,
+    view: T,
+}
+
+impl<R, T: traits::PeRead> ExportIterator<R, T> {
+    pub fn new(functions: R, view: T, directory: image::ExportDirectory, data: image::DataDirectory) -> io::Result<Self> {
+        let names = try!(export_names(&view, &directory));
+
+        Ok(ExportIterator {
+            fuse: false,
+            index: 0,
+            directory: directory,
+            data: data,
+            names: names,
+            read: functions,
+            view: view,
+        })
+    }
+
+    pub fn directory(&self) -> &image::ExportDirectory {
+        &self.directory
+    }
+}
+
+impl<R: Read, T: traits::PeRead> ExportIterator<R, T> {
+    fn try_next(&mut self) -> io::Result<Option<nt::Export>> {
+        while self.index < self.directory.number_of_functions() {
+            let index = self.index;
+            self.index += 1;
+
+            let address = try!(read_u32(&mut self.read));
+            if address == 0 {
+                continue
+            }
+
+            let name = match self.names.binary_search_by_key(&index, |&(index, _)| index) {
+                Ok(i) => Some(try!(self.view.read_cstring(self.names[i].1))),
+                Err(..) => None,
+            };
+
+            return Ok(Some(nt::Export {
+                ordinal: try!(export_ordinal(&self.directory, index)),
+                name: name,
+                address: try!(export_address(&self.view, &self.data, address)),
+            }))
+        }
+
+        Ok(None)
+    }
+}
+
+impl<R: Read, T: traits::PeRead> Iterator for ExportIterator<R, T> {
+    type Item = io::Result<nt::Export>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.fuse {
@@ -177,6 +371,302 @@ impl<R: Read, T: traits::PeRead> Iterator for ImportTableIterator<R, T> {
     }
 }
 
+pub fn export_directory<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<(image::ExportDirectory, image::DataDirectory)> {
+    let data = *try!(view.directory_header(nt::DirectoryEntry::Export)
+        .ok_or_else(|| invalid_data("export segment not found"))
+    );
+
+    view.directory(&data)
+        .and_then(|mut read| read.read_pod())
+        .map(|directory| (directory, data))
+}
+
+pub fn find_export_by_name<T: traits::PeRead + ?Sized>(view: &T, directory: &image::ExportDirectory, data: &image::DataDirectory, name: &CStr, hint: Option<u16>) -> io::Result<Option<nt::Export>> {
+    let count = directory.number_of_names();
+    let name_at = |index: u32| export_table_entry(directory.address_of_names(), index, size_of::<u32>())
+        .and_then(|address| view.segment(address, size_of::<u32>() as _))
+        .and_then(read_u32)
+        .and_then(|address| view.read_cstring(address));
+    let function_at = |index: u32| export_table_entry(directory.address_of_name_ordinals(), index, size_of::<u16>())
+        .and_then(|address| view.segment(address, size_of::<u16>() as _))
+        .and_then(read_u16);
+
+    if let Some(hint) = hint.map(|hint| hint as u32).and_then(|hint| if hint < count { Some(hint) } else { None }) {
+        if try!(name_at(hint)).as_bytes() == name.to_bytes() {
+            let index = try!(function_at(hint));
+            return export_entry(view, directory, data, index as u32, Some(name.to_owned()))
+        }
+    }
+
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match try!(name_at(mid)).as_bytes().cmp(name.to_bytes()) {
+            cmp::Ordering::Less => low = mid + 1,
+            cmp::Ordering::Greater => high = mid,
+            cmp::Ordering::Equal => {
+                let index = try!(function_at(mid));
+                return export_entry(view, directory, data, index as u32, Some(name.to_owned()))
+            },
+        }
+    }
+
+    Ok(None)
+}
+
+pub fn find_export_by_ordinal<T: traits::PeRead + ?Sized>(view: &T, directory: &image::ExportDirectory, data: &image::DataDirectory, ordinal: u16) -> io::Result<Option<nt::Export>> {
+    let index = match (ordinal as u32).checked_sub(directory.base()) {
+        Some(index) if index < directory.number_of_functions() => index,
+        _ => return Ok(None),
+    };
+
+    let count = directory.number_of_names();
+    let name = if count > 0 {
+        let mut ordinals = try!(count.checked_mul(size_of::<u16>() as u32)
+            .ok_or_else(|| invalid_data("bad export name count"))
+            .and_then(|size| view.segment(directory.address_of_name_ordinals(), size))
+        );
+        let mut name = None;
+        for i in 0..count {
+            if try!(read_u16(&mut ordinals)) as u32 == index {
+                let address = try!(export_table_entry(directory.address_of_names(), i, size_of::<u32>())
+                    .and_then(|address| view.segment(address, size_of::<u32>() as _))
+                    .and_then(read_u32)
+                );
+                name = Some(try!(view.read_cstring(address)));
+                break
+            }
+        }
+        name
+    } else {
+        None
+    };
+
+    export_entry(view, directory, data, index, name)
+}
+
+fn export_entry<T: traits::PeRead + ?Sized>(view: &T, directory: &image::ExportDirectory, data: &image::DataDirectory, index: u32, name: Option<CString>) -> io::Result<Option<nt::Export>> {
+    if index >= directory.number_of_functions() {
+        return Err(invalid_data("bad export name ordinal"))
+    }
+
+    let address = try!(export_table_entry(directory.address_of_functions(), index, size_of::<u32>())
+        .and_then(|address| view.segment(address, size_of::<u32>() as _))
+        .and_then(read_u32)
+    );
+
+    if address == 0 {
+        return Ok(None)
+    }
+
+    Ok(Some(nt::Export {
+        ordinal: try!(export_ordinal(directory, index)),
+        name: name,
+        address: try!(export_address(view, data, address)),
+    }))
+}
+
+fn export_names<T: traits::PeRead + ?Sized>(view: &T, directory: &image::ExportDirectory) -> io::Result<Vec<(u32, u32)>> {
+    let count = directory.number_of_names();
+    if count == 0 {
+        return Ok(Vec::new())
+    }
+
+    let mut ordinals = try!(count.checked_mul(size_of::<u16>() as u32)
+        .ok_or_else(|| invalid_data("bad export name count"))
+        .and_then(|size| view.segment(directory.address_of_name_ordinals(), size))
+    );
+    let mut names = try!(count.checked_mul(size_of::<u32>() as u32)
+        .ok_or_else(|| invalid_data("bad export name count"))
+        .and_then(|size| view.segment(directory.address_of_names(), size))
+    );
+
+    let mut entries = try!((0..count).map(|_|
+        read_u16(&mut ordinals).and_then(|ordinal|
+            read_u32(&mut names).map(|name| (ordinal as u32, name))
+        )
+    ).collect::<Result<Vec<_>, _>>());
+
+    entries.sort();
+
+    Ok(entries)
+}
+
+fn export_table_entry(address: u32, index: u32, size: usize) -> io::Result<u32> {
+    index.checked_mul(size as u32)
+        .and_then(|offset| address.checked_add(offset))
+        .ok_or_else(|| invalid_data("bad export table address"))
+}
+
+fn export_ordinal(directory: &image::ExportDirectory, index: u32) -> io::Result<u16> {
+    directory.base().checked_add(index)
+        .and_then(|ordinal| if ordinal <= u16::max_value() as u32 {
+            Some(ordinal as u16)
+        } else {
+            None
+        }).ok_or_else(|| invalid_data("bad export ordinal"))
+}
+
+fn export_address<T: traits::PeRead + ?Sized>(view: &T, data: &image::DataDirectory, address: u32) -> io::Result<nt::ExportAddress> {
+    if address >= data.virtual_address() && address - data.virtual_address() < data.size() {
+        view.read_cstring(address)
+            .and_then(|forwarder| parse_forwarder(forwarder.as_bytes()))
+            .map(nt::ExportAddress::Forwarder)
+    } else {
+        Ok(nt::ExportAddress::Rva(address))
+    }
+}
+
+pub fn parse_forwarder(forwarder: &[u8]) -> io::Result<nt::Forwarder> {
+    let split = try!(forwarder.iter().rposition(|&c| c == b'.')
+        .ok_or_else(|| invalid_data("export forwarder missing module"))
+    );
+    let (module, symbol) = (&forwarder[..split], &forwarder[split + 1..]);
+
+    if module.is_empty() || symbol.is_empty() {
+        return Err(invalid_data("bad export forwarder"))
+    }
+
+    let symbol = if symbol[0] == b'#' {
+        try!(str::from_utf8(&symbol[1..]).ok()
+            .and_then(|ordinal| ordinal.parse().ok())
+            .map(nt::ForwarderSymbol::Ordinal)
+            .ok_or_else(|| invalid_data("bad export forwarder ordinal"))
+        )
+    } else {
+        nt::ForwarderSymbol::Name(try!(CString::new(symbol)
+            .map_err(|_| invalid_data("bad export forwarder name"))
+        ))
+    };
+
+    Ok(nt::Forwarder {
+        module: try!(CString::new(module)
+            .map_err(|_| invalid_data("bad export forwarder module"))
+        ),
+        symbol: symbol,
+    })
+}
+
+pub fn module_name_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    fn strip(name: &[u8]) -> &[u8] {
+        let len = name.len();
+        if len > 4 && name[len - 4..].eq_ignore_ascii_case(b".dll") {
+            &name[..len - 4]
+        } else {
+            name
+        }
+    }
+
+    strip(lhs).eq_ignore_ascii_case(strip(rhs))
+}
+
+pub fn resolve_export<T: traits::PeRead>(modules: &[(&str, T)], module: &str, symbol: &nt::ImportSymbol) -> io::Result<Option<nt::ResolvedExport>> {
+    let mut module = try!(modules.iter().position(|&(name, _)| module_name_eq(name.as_bytes(), module.as_bytes()))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "export module not found"))
+    );
+    let mut symbol = symbol.clone();
+    let mut visited = Vec::new();
+
+    loop {
+        if visited.iter().any(|&(m, ref s)| m == module && *s == symbol) {
+            return Err(invalid_data("export forwarder cycle"))
+        }
+
+        let export = match try!(modules[module].1.find_export(&symbol)) {
+            Some(export) => export,
+            None => return Ok(None),
+        };
+
+        match export.address {
+            nt::ExportAddress::Rva(address) => return Ok(Some(nt::ResolvedExport {
+                module: module,
+                address: address,
+            })),
+            nt::ExportAddress::Forwarder(forwarder) => {
+                visited.push((module, symbol));
+
+                module = try!(modules.iter().position(|&(name, _)| module_name_eq(name.as_bytes(), forwarder.module.as_bytes()))
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "forwarded export module not found"))
+                );
+                symbol = forwarder.symbol.into();
+            },
+        }
+    }
+}
+
+pub fn va_to_rva<T: traits::PeHeaders + ?Sized>(view: &T, va: u64) -> io::Result<u32> {
+    va.checked_sub(view.nt_headers().image_base())
+        .and_then(|rva| if rva <= u32::max_value() as u64 { Some(rva as u32) } else { None })
+        .ok_or_else(|| invalid_data("virtual address outside of image"))
+}
+
+fn read_va<R: Read>(kind: nt::NtKind, mut read: R) -> io::Result<u64> {
+    match kind {
+        nt::NtKind::Win32 => read_u32(read).map(|va| va as u64),
+        nt::NtKind::Win64 => read.read_pod::<u64>().map(u64::from_le),
+    }
+}
+
+pub fn tls<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<nt::Tls> {
+    let data = try!(view.directory_header(nt::DirectoryEntry::Tls)
+        .ok_or_else(|| invalid_data("tls segment not found"))
+    );
+
+    let directory: image::TlsDirectory64 = try!(match view.kind() {
+        nt::NtKind::Win32 => view.segment(data.virtual_address(), size_of::<image::TlsDirectory32>() as u32)
+            .and_then(|mut r| r.read_pod::<image::TlsDirectory32>()).map(From::from),
+        nt::NtKind::Win64 => view.segment(data.virtual_address(), size_of::<image::TlsDirectory64>() as u32)
+            .and_then(|mut r| r.read_pod()),
+    });
+
+    // an empty template is allowed to leave its bounds unset
+    let rva = |va: u64| if va == 0 { Ok(0) } else { va_to_rva(view, va) };
+
+    let mut callbacks = Vec::new();
+    if directory.address_of_callbacks() != 0 {
+        let mut read = try!(view.segment_from(try!(va_to_rva(view, directory.address_of_callbacks()))));
+        loop {
+            match try!(read_va(view.kind(), &mut read)) {
+                0 => break,
+                va => callbacks.push(try!(va_to_rva(view, va))),
+            }
+        }
+    }
+
+    Ok(nt::Tls {
+        raw_data_start: try!(rva(directory.start_address_of_raw_data())),
+        raw_data_end: try!(rva(directory.end_address_of_raw_data())),
+        address_of_index: try!(rva(directory.address_of_index())),
+        callbacks: callbacks,
+        size_of_zero_fill: directory.size_of_zero_fill(),
+        characteristics: directory.characteristics(),
+    })
+}
+
+fn read_load_config<T: traits::PeRead + ?Sized, P: Pod>(view: &T, address: u32, size: u32) -> io::Result<P> {
+    if (size as usize) < size_of::<u32>() {
+        return Err(invalid_data("bad load config size"))
+    }
+
+    // newer fields stay zeroed when the image predates them
+    let mut config: P = Pod::zeroed();
+    let len = cmp::min(size as usize, size_of::<P>());
+    try!(view.segment(address, len as u32).and_then(|mut r| r.read_exact(&mut config.as_bytes_mut()[..len])));
+    Ok(config)
+}
+
+pub fn load_config<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<nt::LoadConfig> {
+    let address = try!(view.directory_header(nt::DirectoryEntry::LoadConfig)
+        .ok_or_else(|| invalid_data("load config segment not found"))
+    ).virtual_address();
+
+    let size = try!(view.segment(address, size_of::<u32>() as u32).and_then(read_u32));
+    match view.kind() {
+        nt::NtKind::Win32 => read_load_config(view, address, size).map(nt::LoadConfig::Win32),
+        nt::NtKind::Win64 => read_load_config(view, address, size).map(nt::LoadConfig::Win64),
+    }
+}
+
 pub fn validate_headers<R: io::Read>(mut r: R) -> io::Result<()> {
     let dos: image::DosHeader = try!(r.read_pod());
 
@@ -361,7 +851,14 @@ pub fn read_headers<R: io::Read>(mut r: R) -> io::Result<(image::DosHeader, Vec<
     Ok((dos, dos_stub, nt, data_directories, sections))
 }
 
-pub fn write_pe<R: traits::PeRead + ?Sized, W: io::Write>(read: &R, mut write: W, raw: bool) -> io::Result<()> {
+pub fn section_data_len(section: &image::SectionHeader) -> u32 {
+    match section.virtual_size() {
+        0 => section.size_of_raw_data(),
+        size => cmp::min(size, section.size_of_raw_data()),
+    }
+}
+
+pub fn write_headers<R: traits::PeHeaders + ?Sized, W: io::Write>(read: &R, mut write: W) -> io::Result<()> {
     try!(write.write_pod(read.dos_header()));
 
     try!(write.write_all(read.dos_stub()));
@@ -379,6 +876,12 @@ pub fn write_pe<R: traits::PeRead + ?Sized, W: io::Write>(read: &R, mut write: W
         try!(write.write_pod(header));
     }
 
+    Ok(())
+}
+
+pub fn write_pe<R: traits::PeRead + ?Sized, W: io::Write>(read: &R, mut write: W, raw: bool) -> io::Result<()> {
+    try!(write_headers(read, &mut write));
+
     let mut pos = read.dos_header().lfanew() + read.nt_headers().len() as u32;
 
     let mut sections: Vec<_> = read.section_headers().into_iter().collect();
@@ -421,3 +924,243 @@ pub struct FileHeader {
 }
 
 unsafe impl Pod for FileHeader { }
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::mem::size_of;
+    use testing::{ImageBuilder, ExportTarget, export_directory, put_u32};
+    use traits::PeRead;
+    use {nt, image, View};
+    use super::*;
+
+    #[test]
+    fn forwarder_name() {
+        assert_eq!(parse_forwarder(b"NTDLL.RtlAllocateHeap").unwrap(), nt::Forwarder {
+            module: CString::new("NTDLL").unwrap(),
+            symbol: nt::ForwarderSymbol::Name(CString::new("RtlAllocateHeap").unwrap()),
+        });
+    }
+
+    #[test]
+    fn forwarder_ordinal() {
+        assert_eq!(parse_forwarder(b"api-ms-win-core-x.dll.#12").unwrap(), nt::Forwarder {
+            module: CString::new("api-ms-win-core-x.dll").unwrap(),
+            symbol: nt::ForwarderSymbol::Ordinal(12),
+        });
+        assert!(parse_forwarder(b"NTDLL.#").is_err());
+        assert!(parse_forwarder(b"RtlAllocateHeap").is_err());
+    }
+
+    #[test]
+    fn module_names() {
+        assert!(module_name_eq(b"NTDLL", b"ntdll.dll"));
+        assert!(module_name_eq(b"api-ms-win-core-x.dll", b"API-MS-WIN-CORE-X"));
+        assert!(!module_name_eq(b"kernel32", b"kernelbase"));
+    }
+
+    fn exports<F: Fn(&mut [u8])>(patch: F) -> Vec<u8> {
+        let mut data = export_directory(0x1000, "a.dll", &[
+            ("Bar", ExportTarget::Rva(0x1100)),
+            ("", ExportTarget::Rva(0x1200)),
+            ("Foo", ExportTarget::Rva(0x1300)),
+            ("Alpha", ExportTarget::Rva(0x1400)),
+        ]);
+        patch(&mut data);
+
+        ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::Export, 0x1000, data.len() as u32)
+            .section(0x1000, data)
+            .build()
+    }
+
+    #[test]
+    fn export_lookup() {
+        let image = exports(|_| ());
+        let view = View::new(&image[..]).unwrap();
+        let foo = nt::Export {
+            ordinal: 3,
+            name: Some(CString::new("Foo").unwrap()),
+            address: nt::ExportAddress::Rva(0x1300),
+        };
+        let name = CString::new("Foo").unwrap();
+
+        // the name table is sorted Alpha, Bar, Foo
+        assert_eq!(view.export_by_name(&name, None).unwrap(), Some(foo.clone()));
+        assert_eq!(view.export_by_name(&name, Some(2)).unwrap(), Some(foo.clone()));
+        assert_eq!(view.export_by_name(&name, Some(0)).unwrap(), Some(foo.clone()));
+        assert_eq!(view.export_by_name(&name, Some(100)).unwrap(), Some(foo.clone()));
+        assert_eq!(view.export_by_name(&CString::new("Alpha").unwrap(), None).unwrap().map(|export| export.ordinal), Some(4));
+        assert_eq!(view.export_by_name(&CString::new("Baz").unwrap(), Some(1)).unwrap(), None);
+        assert_eq!(view.export_by_name(&CString::new("Zzz").unwrap(), None).unwrap(), None);
+
+        assert_eq!(view.export_by_ordinal(3).unwrap(), Some(foo));
+        assert_eq!(view.export_by_ordinal(2).unwrap(), Some(nt::Export {
+            ordinal: 2,
+            name: None,
+            address: nt::ExportAddress::Rva(0x1200),
+        }));
+        assert_eq!(view.export_by_ordinal(0).unwrap(), None);
+        assert_eq!(view.export_by_ordinal(5).unwrap(), None);
+    }
+
+    #[test]
+    fn export_lookup_overflow() {
+        let name = CString::new("Foo").unwrap();
+
+        let image = exports(|data| put_u32(data, 0x20, 0xfffffffc));
+        assert!(View::new(&image[..]).unwrap().export_by_name(&name, None).is_err());
+
+        let image = exports(|data| put_u32(data, 0x24, 0xfffffffe));
+        assert!(View::new(&image[..]).unwrap().export_by_name(&name, Some(2)).is_err());
+
+        let image = exports(|data| put_u32(data, 0x18, 0x80000001));
+        assert!(View::new(&image[..]).unwrap().export_by_ordinal(1).is_err());
+
+        let image = exports(|data| put_u32(data, 0x1c, 0xfffffffc));
+        assert!(View::new(&image[..]).unwrap().export_by_ordinal(2).is_err());
+    }
+
+    #[test]
+    fn forwarder_chains() {
+        let module = |name: &str, exports: &[(&str, ExportTarget)]| {
+            let data = export_directory(0x1000, name, exports);
+            ImageBuilder::new(nt::NtKind::Win32)
+                .directory(nt::DirectoryEntry::Export, 0x1000, data.len() as u32)
+                .section(0x1000, data)
+                .build()
+        };
+        let a = module("a.dll", &[("X", ExportTarget::Forwarder("b.Y")), ("Cycle", ExportTarget::Forwarder("b.Loop"))]);
+        let b = module("b.dll", &[("Y", ExportTarget::Forwarder("c.#2")), ("Loop", ExportTarget::Forwarder("a.Cycle"))]);
+        let c = module("c.dll", &[("Z", ExportTarget::Rva(0x1100)), ("W", ExportTarget::Rva(0x1234))]);
+        let modules = [
+            ("a.dll", View::new(&a[..]).unwrap()),
+            ("b.dll", View::new(&b[..]).unwrap()),
+            ("c.dll", View::new(&c[..]).unwrap()),
+        ];
+        let symbol = |name: &str| nt::ImportSymbol::Name { ordinal_hint: 0, name: CString::new(name).unwrap() };
+
+        assert_eq!(resolve_export(&modules, "a", &symbol("X")).unwrap(), Some(nt::ResolvedExport {
+            module: 2,
+            address: 0x1234,
+        }));
+        assert_eq!(resolve_export(&modules, "a", &symbol("Missing")).unwrap(), None);
+
+        let err = resolve_export(&modules, "a", &symbol("Cycle")).unwrap_err();
+        assert_eq!((err.kind(), err.to_string()), (io::ErrorKind::InvalidData, "export forwarder cycle".to_owned()));
+        assert_eq!(resolve_export(&modules[..2], "a", &symbol("X")).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn tls_callbacks() {
+        let mut data = vec![0u8; 0x30];
+        for (i, &va) in [0x10003000, 0x10003010, 0x10003020, 0x10002020].iter().enumerate() {
+            put_u32(&mut data, i * 4, va);
+        }
+        put_u32(&mut data, 0x10, 0x20);
+        put_u32(&mut data, 0x20, 0x10001000);
+        put_u32(&mut data, 0x24, 0x10001010);
+
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::Tls, 0x2000, 0x18)
+            .section(0x1000, vec![0xc3; 0x20])
+            .section(0x2000, data)
+            .section(0x3000, vec![0; 0x24])
+            .build();
+        let view = View::new(&image[..]).unwrap();
+
+        assert_eq!(view.tls().unwrap(), nt::Tls {
+            raw_data_start: 0x3000,
+            raw_data_end: 0x3010,
+            address_of_index: 0x3020,
+            callbacks: vec![0x1000, 0x1010],
+            size_of_zero_fill: 0x20,
+            characteristics: 0,
+        });
+    }
+
+    #[test]
+    fn load_config_size() {
+        assert_eq!(size_of::<image::LoadConfigDirectory32>(), 0xc0);
+        assert_eq!(size_of::<image::LoadConfigDirectory64>(), 0x140);
+
+        let mut data = vec![0u8; 0x60];
+        put_u32(&mut data, 0, 0x48);
+        put_u32(&mut data, 0x3c, 0x10003000);
+        put_u32(&mut data, 0x40, 0x10002100);
+        put_u32(&mut data, 0x44, 2);
+        // beyond the declared size, so this must not be picked up as GuardFlags
+        put_u32(&mut data, 0x58, 0xffffffff);
+
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::LoadConfig, 0x2000, 0x40)
+            .section(0x2000, data)
+            .build();
+        let view = View::new(&image[..]).unwrap();
+        let config = view.load_config().unwrap();
+
+        assert_eq!(config.size(), 0x48);
+        assert_eq!(config.security_cookie(), Some(0x10003000));
+        assert_eq!(config.se_handler_table(), Some(0x10002100));
+        assert_eq!(config.se_handler_count(), Some(2));
+        assert_eq!(config.guard_cf_check_function_pointer(), None);
+        assert_eq!(config.guard_flags(), None);
+        assert_eq!(config.code_integrity(), None);
+    }
+
+    #[test]
+    fn delay_imports() {
+        let mut data = vec![0u8; 0x400];
+        for (i, &value) in [1, 0x2100, 0x2180, 0x2200, 0x2140, 0, 0, 0].iter().enumerate() {
+            put_u32(&mut data, i * 4, value);
+        }
+        for (i, &value) in [0, 0x10002110, 0x10002184, 0x10002210, 0x10002160, 0, 0, 0x12345678].iter().enumerate() {
+            put_u32(&mut data, 0x20 + i * 4, value);
+        }
+        data[0x100..0x106].copy_from_slice(b"a.dll\0");
+        data[0x110..0x116].copy_from_slice(b"b.dll\0");
+        put_u32(&mut data, 0x140, 0x2300);
+        put_u32(&mut data, 0x144, 0x80000007);
+        put_u32(&mut data, 0x160, 0x10002310);
+        data[0x300..0x306].copy_from_slice(b"\x01\0Foo\0");
+        data[0x310..0x316].copy_from_slice(b"\x02\0Bar\0");
+
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::DelayImport, 0x2000, 0x60)
+            .section(0x2000, data)
+            .build();
+        let view = View::new(&image[..]).unwrap();
+
+        let imports = view.delay_imports().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(imports.len(), 2);
+        assert!(imports[0].rva_based() && !imports[1].rva_based());
+        assert_eq!(imports[1].dll_name_rva(), 0x2110);
+        assert_eq!(imports[1].module_handle_rva(), 0x2184);
+        assert_eq!(imports[1].import_address_table_rva(), 0x2210);
+        assert_eq!(imports[1].bound_import_address_table_rva(), 0);
+        assert_eq!(imports[1].time_date_stamp(), 0x12345678);
+
+        let names = imports.iter().map(|import| view.read_cstring(import.dll_name_rva()).unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, vec![CString::new("a.dll").unwrap(), CString::new("b.dll").unwrap()]);
+
+        let thunks = view.delay_import_table(&imports[0]).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(thunks, vec![
+            nt::ImportThunk {
+                symbol: nt::ImportSymbol::Name { ordinal_hint: 1, name: CString::new("Foo").unwrap() },
+                int_rva: Some(0x2140),
+                iat_rva: 0x2200,
+                iat_value: 0,
+            },
+            nt::ImportThunk {
+                symbol: nt::ImportSymbol::Ordinal(7),
+                int_rva: Some(0x2144),
+                iat_rva: 0x2204,
+                iat_value: 0,
+            },
+        ]);
+        let symbols = view.delay_import_table(&imports[1]).unwrap().map(|thunk| thunk.map(|thunk| thunk.symbol)).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(symbols, vec![
+            nt::ImportSymbol::Name { ordinal_hint: 2, name: CString::new("Bar").unwrap() },
+        ]);
+    }
+}
@@ -0,0 +1,786 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem::size_of;
+use std::cmp;
+use std::collections::{BTreeMap, HashSet};
+use pod::{Pod, PodReadExt, PodWriteExt};
+use result::OptionResultExt;
+use {nt, image, parse, traits};
+
+const RESOURCE_DEPTH: usize = 3;
+
+struct ResourceFrame {
+    offset: u32,
+    index: u32,
+    count: u32,
+    id: Option<nt::ResourceId>,
+}
+
+pub struct ResourceIterator<T> {
+    fuse: bool,
+    data: image::DataDirectory,
+    stack: Vec<ResourceFrame>,
+    visited: HashSet<u32>,
+    view: T,
+}
+
+impl<T: traits::PeRead> ResourceIterator<T> {
+    pub fn new(view: T, data: image::DataDirectory) -> io::Result<Self> {
+        let mut iter = ResourceIterator {
+            fuse: false,
+            data: data,
+            stack: Vec::new(),
+            visited: HashSet::new(),
+            view: view,
+        };
+
+        try!(iter.push_directory(0, None));
+
+        Ok(iter)
+    }
+
+    fn read<P: Pod>(&self, offset: u32) -> io::Result<P> {
+        offset.checked_add(size_of::<P>() as u32)
+            .and_then(|end| if end <= self.data.size() {
+                Some(self.data.virtual_address() + offset)
+            } else {
+                None
+            }).ok_or_else(|| parse::invalid_data("resource offset out of bounds"))
+            .and_then(|address| self.view.segment(address, size_of::<P>() as _))
+            .and_then(|mut read| read.read_pod())
+    }
+
+    fn read_string(&self, offset: u32) -> io::Result<String> {
+        let string: image::ResourceDirectoryString = try!(self.read(offset));
+        let offset = offset + size_of::<image::ResourceDirectoryString>() as u32;
+        let name = try!((0..string.length() as u32).map(|i| self.read::<u16>(offset + i * 2).map(u16::from_le))
+            .collect::<Result<Vec<_>, _>>()
+        );
+
+        String::from_utf16(&name).map_err(|_| parse::invalid_data("bad resource name"))
+    }
+
+    fn push_directory(&mut self, offset: u32, id: Option<nt::ResourceId>) -> io::Result<()> {
+        if self.stack.len() >= RESOURCE_DEPTH {
+            return Err(parse::invalid_data("resource directory too deep"))
+        }
+
+        if !self.visited.insert(offset) {
+            return Err(parse::invalid_data("resource directory cycle"))
+        }
+
+        let directory: image::ResourceDirectory = try!(self.read(offset));
+        self.stack.push(ResourceFrame {
+            offset: offset,
+            index: 0,
+            count: directory.number_of_entries(),
+            id: id,
+        });
+
+        Ok(())
+    }
+
+    fn try_next(&mut self) -> io::Result<Option<nt::Resource>> {
+        loop {
+            let (offset, index) = match self.stack.last_mut() {
+                Some(ref mut frame) if frame.index < frame.count => {
+                    frame.index += 1;
+                    (frame.offset, frame.index - 1)
+                },
+                Some(..) => {
+                    self.stack.pop();
+                    continue
+                },
+                None => return Ok(None),
+            };
+
+            let entry: image::ResourceDirectoryEntry = try!(self.read(
+                offset + size_of::<image::ResourceDirectory>() as u32 + index * size_of::<image::ResourceDirectoryEntry>() as u32
+            ));
+
+            let id = if entry.name_is_string() {
+                nt::ResourceId::Name(try!(self.read_string(entry.name_offset())))
+            } else {
+                nt::ResourceId::Id(entry.id())
+            };
+
+            if entry.data_is_directory() {
+                try!(self.push_directory(entry.offset_to_directory(), Some(id)));
+                continue
+            }
+
+            if self.stack.len() != RESOURCE_DEPTH {
+                return Err(parse::invalid_data("resource data entry at bad depth"))
+            }
+
+            let language = match id {
+                nt::ResourceId::Id(language) => language,
+                nt::ResourceId::Name(..) => return Err(parse::invalid_data("bad resource language")),
+            };
+
+            return Ok(Some(nt::Resource {
+                kind: self.stack[1].id.clone().unwrap(),
+                name: self.stack[2].id.clone().unwrap(),
+                language: language,
+                data: try!(self.read(entry.offset_to_data())),
+            }))
+        }
+    }
+}
+
+impl<T: traits::PeRead> Iterator for ResourceIterator<T> {
+    type Item = io::Result<nt::Resource>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceData {
+    pub data: Vec<u8>,
+    pub code_page: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceTable {
+    entries: BTreeMap<(nt::ResourceId, nt::ResourceId, u16), ResourceData>,
+}
+
+impl ResourceTable {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn from_image<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<Self> {
+        let mut table = ResourceTable::new();
+
+        if view.directory_header(nt::DirectoryEntry::Resource).is_none() {
+            return Ok(table)
+        }
+
+        for resource in try!(view.resources()) {
+            let resource = try!(resource);
+            let mut data = Vec::new();
+            try!(view.resource_data(&resource).and_then(|mut r| r.read_to_end(&mut data)));
+
+            table.insert(resource.kind, resource.name, resource.language, ResourceData {
+                data: data,
+                code_page: resource.data.code_page(),
+            });
+        }
+
+        Ok(table)
+    }
+
+    pub fn get(&self, kind: &nt::ResourceId, name: &nt::ResourceId, language: u16) -> Option<&ResourceData> {
+        self.entries.get(&(kind.clone(), name.clone(), language))
+    }
+
+    pub fn insert(&mut self, kind: nt::ResourceId, name: nt::ResourceId, language: u16, data: ResourceData) -> Option<ResourceData> {
+        self.entries.insert((kind, name, language), data)
+    }
+
+    pub fn remove(&mut self, kind: &nt::ResourceId, name: &nt::ResourceId, language: u16) -> Option<ResourceData> {
+        self.entries.remove(&(kind.clone(), name.clone(), language))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> ::std::collections::btree_map::Iter<(nt::ResourceId, nt::ResourceId, u16), ResourceData> {
+        self.entries.iter()
+    }
+
+    fn tree(&self) -> Vec<(&nt::ResourceId, Vec<(&nt::ResourceId, Vec<(u16, &ResourceData)>)>)> {
+        let mut tree: Vec<(&nt::ResourceId, Vec<(&nt::ResourceId, Vec<(u16, &ResourceData)>)>)> = Vec::new();
+
+        for (&(ref kind, ref name, language), data) in &self.entries {
+            if tree.last().map(|&(k, _)| k != kind).unwrap_or(true) {
+                tree.push((kind, Vec::new()));
+            }
+            let names = &mut tree.last_mut().unwrap().1;
+            if names.last().map(|&(n, _)| n != name).unwrap_or(true) {
+                names.push((name, Vec::new()));
+            }
+            names.last_mut().unwrap().1.push((language, data));
+        }
+
+        // the loader binary searches named entries case-insensitively
+        tree.sort_by(|&(lhs, _), &(rhs, _)| resource_id_cmp(lhs, rhs));
+        for &mut (_, ref mut names) in &mut tree {
+            names.sort_by(|&(lhs, _), &(rhs, _)| resource_id_cmp(lhs, rhs));
+        }
+
+        tree
+    }
+
+    pub fn build(&self, address: u32) -> io::Result<Vec<u8>> {
+        let tree = self.tree();
+        let directory_size = |entries: usize| (size_of::<image::ResourceDirectory>() + entries * size_of::<image::ResourceDirectoryEntry>()) as u32;
+
+        let mut offset = directory_size(tree.len());
+        let mut name_directories = Vec::new();
+        for &(_, ref names) in &tree {
+            name_directories.push(offset);
+            offset += directory_size(names.len());
+        }
+        let mut language_directories = Vec::new();
+        for &(_, ref names) in &tree {
+            for &(_, ref languages) in names {
+                language_directories.push(offset);
+                offset += directory_size(languages.len());
+            }
+        }
+        let mut data_entries = offset;
+        offset += (self.entries.len() * size_of::<image::ResourceDataEntry>()) as u32;
+        let mut strings = offset;
+        for &(kind, ref names) in &tree {
+            offset += resource_id_size(kind);
+            for &(name, _) in names {
+                offset += resource_id_size(name);
+            }
+        }
+        let mut data_offset = try!(parse::align_up(offset, 8)
+            .ok_or_else(|| parse::invalid_data("resource section too large"))
+        );
+
+        let mut out = Vec::new();
+        let mut string_data = Vec::new();
+        let mut blob_data = Vec::new();
+        let mut data_entry_data = Vec::new();
+
+        {
+            let mut entry = |out: &mut Vec<u8>, id: &nt::ResourceId, target: u32| -> io::Result<()> {
+                let mut entry: image::ResourceDirectoryEntry = Pod::zeroed();
+                entry.name.set(match *id {
+                    nt::ResourceId::Id(id) => id as u32,
+                    nt::ResourceId::Name(ref name) => {
+                        let string_offset = strings;
+                        let name: Vec<u16> = name.encode_utf16().collect();
+                        if name.len() > u16::max_value() as usize {
+                            return Err(parse::invalid_data("resource name too long"))
+                        }
+                        try!(string_data.write_pod(&(name.len() as u16).to_le()));
+                        for c in name {
+                            try!(string_data.write_pod(&c.to_le()));
+                        }
+                        strings += resource_id_size(id);
+                        string_offset | image::RESOURCE_NAME_IS_STRING
+                    },
+                });
+                entry.offset_to_data.set(target);
+                out.write_pod(&entry)
+            };
+
+            let mut languages = language_directories.iter();
+            try!(out.write_pod(&resource_directory(tree.iter().map(|&(kind, _)| kind))));
+            for (&(kind, _), &directory) in tree.iter().zip(&name_directories) {
+                try!(entry(&mut out, kind, directory | image::RESOURCE_DATA_IS_DIRECTORY));
+            }
+            for &(_, ref names) in &tree {
+                try!(out.write_pod(&resource_directory(names.iter().map(|&(name, _)| name))));
+                for (&(name, _), &directory) in names.iter().zip(languages.by_ref()) {
+                    try!(entry(&mut out, name, directory | image::RESOURCE_DATA_IS_DIRECTORY));
+                }
+            }
+            for &(_, ref names) in &tree {
+                for &(_, ref languages) in names {
+                    let ids: Vec<_> = languages.iter().map(|&(language, _)| nt::ResourceId::Id(language)).collect();
+                    try!(out.write_pod(&resource_directory(ids.iter())));
+                    for (id, &(_, data)) in ids.iter().zip(languages) {
+                        try!(entry(&mut out, id, data_entries));
+                        data_entries += size_of::<image::ResourceDataEntry>() as u32;
+
+                        if data.data.len() > u32::max_value() as usize {
+                            return Err(parse::invalid_data("resource too large"))
+                        }
+                        let size = data.data.len() as u32;
+                        let mut data_entry: image::ResourceDataEntry = Pod::zeroed();
+                        data_entry.offset_to_data.set(try!(address.checked_add(data_offset)
+                            .ok_or_else(|| parse::invalid_data("resource section too large"))
+                        ));
+                        data_entry.size.set(size);
+                        data_entry.code_page.set(data.code_page);
+                        try!(data_entry_data.write_pod(&data_entry));
+
+                        blob_data.extend_from_slice(&data.data);
+                        let padding = try!(parse::align_up(size, 8)
+                            .ok_or_else(|| parse::invalid_data("resource too large"))
+                        ) - size;
+                        blob_data.extend((0..padding).map(|_| 0u8));
+                        data_offset = try!(data_offset.checked_add(size + padding)
+                            .ok_or_else(|| parse::invalid_data("resource section too large"))
+                        );
+                    }
+                }
+            }
+        }
+
+        out.extend_from_slice(&data_entry_data);
+        out.extend_from_slice(&string_data);
+        let padding = try!(parse::align_up(out.len() as u32, 8)
+            .ok_or_else(|| parse::invalid_data("resource section too large"))
+        ) as usize - out.len();
+        out.extend((0..padding).map(|_| 0u8));
+        out.extend_from_slice(&blob_data);
+
+        Ok(out)
+    }
+}
+
+fn resource_id_cmp(lhs: &nt::ResourceId, rhs: &nt::ResourceId) -> cmp::Ordering {
+    match (lhs, rhs) {
+        (&nt::ResourceId::Name(ref lhs), &nt::ResourceId::Name(ref rhs)) =>
+            lhs.to_uppercase().cmp(&rhs.to_uppercase()).then_with(|| lhs.cmp(rhs)),
+        _ => lhs.cmp(rhs),
+    }
+}
+
+fn resource_id_size(id: &nt::ResourceId) -> u32 {
+    match *id {
+        nt::ResourceId::Id(..) => 0,
+        nt::ResourceId::Name(ref name) => (size_of::<image::ResourceDirectoryString>() + name.encode_utf16().count() * size_of::<u16>()) as u32,
+    }
+}
+
+fn resource_directory<'a, I: Iterator<Item=&'a nt::ResourceId>>(ids: I) -> image::ResourceDirectory {
+    let mut directory: image::ResourceDirectory = Pod::zeroed();
+    let (mut named, mut id) = (0u16, 0u16);
+    for i in ids {
+        match *i {
+            nt::ResourceId::Name(..) => named += 1,
+            nt::ResourceId::Id(..) => id += 1,
+        }
+    }
+    directory.number_of_named_entries.set(named);
+    directory.number_of_id_entries.set(id);
+    directory
+}
+
+// The aligned end of the last section, or of the headers when there are none
+fn image_end(sections: &[image::SectionHeader], size_of_headers: u32, alignment: u32) -> io::Result<u32> {
+    let mut end = try!(parse::align_up(size_of_headers, alignment)
+        .ok_or_else(|| parse::invalid_data("bad SizeOfHeaders"))
+    );
+    for section in sections {
+        let section_end = try!(section.virtual_address().checked_add(section.virtual_size())
+            .and_then(|end| parse::align_up(end, alignment))
+            .ok_or_else(|| parse::invalid_data("section end out of range"))
+        );
+        end = cmp::max(end, section_end);
+    }
+    Ok(end)
+}
+
+// Sections keep their raw data where it is: the old resources are spliced out of the file and
+// the new ones spliced in, moving only what follows them, overlay included. An old .rsrc that
+// can't grow in place keeps its address range but loses its raw data to a new last section.
+pub fn write_resources<R: Read + Seek, W: Write>(mut read: R, resources: &ResourceTable, mut write: W) -> io::Result<()> {
+    let (dos, dos_stub, nt, mut directories, mut sections) = try!(parse::read_headers(&mut read));
+    let section_alignment = nt.section_alignment();
+    let file_alignment = nt.file_alignment();
+    let size_of_headers = nt.size_of_headers();
+
+    if directories.len() <= nt::DirectoryEntry::Resource as usize {
+        return Err(parse::invalid_data("resource directory entry not present"))
+    }
+    let existing = match directories[nt::DirectoryEntry::Resource as usize] {
+        ref dir if dir.is_empty() => None,
+        ref dir => sections.iter().position(|s| s.virtual_address() == dir.virtual_address()),
+    };
+    let end = sections.iter()
+        .filter(|s| s.size_of_raw_data() != 0)
+        .map(|s| s.pointer_to_raw_data() as u64 + s.size_of_raw_data() as u64)
+        .fold(size_of_headers as u64, cmp::max);
+
+    let mut in_place = None;
+    if let Some(index) = existing {
+        let address = sections[index].virtual_address();
+        let data = try!(resources.build(address));
+        let next = sections.iter().map(|s| s.virtual_address()).filter(|&a| a > address).min();
+        if next.map(|next| address as u64 + data.len() as u64 <= next as u64).unwrap_or(true) {
+            in_place = Some((index, data));
+        }
+    }
+
+    // (file offset, length of old data dropped there, whether the resources go there)
+    let mut edits = Vec::new();
+    let (index, mut data) = match in_place {
+        Some((index, data)) => {
+            let section = &sections[index];
+            edits.push(match section.size_of_raw_data() {
+                0 => (end, 0, true),
+                size => (section.pointer_to_raw_data() as u64, size as u64, true),
+            });
+            (index, data)
+        },
+        None => {
+            let headers_end = dos.lfanew() as usize + nt.len() + size_of::<image::SectionHeader>();
+            if headers_end > size_of_headers as usize {
+                return Err(parse::invalid_data("no room for resource section header"))
+            }
+
+            if let Some(index) = existing {
+                let section = &mut sections[index];
+                if section.size_of_raw_data() != 0 {
+                    edits.push((section.pointer_to_raw_data() as u64, section.size_of_raw_data() as u64, false));
+                }
+                section.pointer_to_raw_data.set(0);
+                section.size_of_raw_data.set(0);
+            }
+
+            let address = try!(image_end(&sections, size_of_headers, section_alignment));
+
+            let mut section: image::SectionHeader = Pod::zeroed();
+            section.name[..5].copy_from_slice(b".rsrc");
+            section.virtual_address.set(address);
+            section.characteristics.set(image::SCN_CNT_INITIALIZED_DATA | image::SCN_MEM_READ);
+            sections.push(section);
+            edits.push((end, 0, true));
+            (sections.len() - 1, try!(resources.build(address)))
+        },
+    };
+
+    // the certificate table can't survive the edit, and conventionally sits last in the file
+    if let Some(dir) = directories.get(nt::DirectoryEntry::Security as usize) {
+        if !dir.is_empty() && dir.virtual_address() as u64 >= end {
+            edits.push((dir.virtual_address() as u64, dir.size() as u64, false));
+        }
+    }
+    edits.sort_by_key(|&(offset, _, _)| offset);
+
+    let size = data.len() as u32;
+    let raw = try!(parse::align_up(size, file_alignment)
+        .ok_or_else(|| parse::invalid_data("resource section too large"))
+    );
+    data.resize(raw as usize, 0);
+
+    let mut out = vec![0u8; size_of_headers as usize];
+    let mut pos = size_of_headers as u64;
+    let mut pointer = 0;
+    for &(offset, len, insert) in &edits {
+        let count = try!(offset.checked_sub(pos).ok_or_else(|| parse::invalid_data("overlapping section data")));
+        try!(read.seek(SeekFrom::Start(pos)));
+        if try!(io::copy(&mut read.by_ref().take(count), &mut out)) != count {
+            return Err(parse::invalid_data("section data beyond end of file"))
+        }
+        if insert {
+            pointer = out.len() as u32;
+            out.extend_from_slice(&data);
+        }
+        pos = offset + len;
+    }
+    try!(read.seek(SeekFrom::Start(pos)));
+    try!(read.read_to_end(&mut out));
+    if out.len() > u32::max_value() as usize {
+        return Err(parse::invalid_data("image too large"))
+    }
+
+    let moved = |offset: u32| edits.iter()
+        .filter(|&&(start, len, _)| start + len <= offset as u64)
+        .fold(offset as u64, |offset, &(_, len, insert)| offset - len + if insert { raw as u64 } else { 0 }) as u32;
+
+    for (i, section) in sections.iter_mut().enumerate() {
+        if i == index {
+            section.virtual_size_physical_address.set(size);
+            section.size_of_raw_data.set(raw);
+            section.pointer_to_raw_data.set(pointer);
+        } else if section.size_of_raw_data() != 0 {
+            let pointer = moved(section.pointer_to_raw_data());
+            section.pointer_to_raw_data.set(pointer);
+        }
+    }
+
+    let size_of_image = try!(image_end(&sections, size_of_headers, section_alignment));
+
+    let address = sections[index].virtual_address();
+    directories[nt::DirectoryEntry::Resource as usize].virtual_address.set(address);
+    directories[nt::DirectoryEntry::Resource as usize].size.set(size);
+    for &entry in &[nt::DirectoryEntry::Security, nt::DirectoryEntry::BoundImport] {
+        if let Some(dir) = directories.get_mut(entry as usize) {
+            *dir = Pod::zeroed();
+        }
+    }
+
+    {
+        let mut headers = io::Cursor::new(&mut out[..size_of_headers as usize]);
+        try!(headers.write_pod(&dos));
+        try!(headers.write_all(&dos_stub));
+        match nt {
+            nt::NtHeaders::Win32(ref nt) => {
+                let mut nt = (**nt).clone();
+                nt.file_header.number_of_sections.set(sections.len() as u16);
+                nt.optional_header.size_of_image.set(size_of_image);
+                try!(headers.write_pod(&nt));
+            },
+            nt::NtHeaders::Win64(ref nt) => {
+                let mut nt = (**nt).clone();
+                nt.file_header.number_of_sections.set(sections.len() as u16);
+                nt.optional_header.size_of_image.set(size_of_image);
+                try!(headers.write_pod(&nt));
+            },
+        }
+        for directory in &directories {
+            try!(headers.write_pod(directory));
+        }
+        let trailing = try!((nt.file_header().size_of_optional_header() as usize)
+            .checked_sub(nt.kind().size_of_optional_header() + directories.len() * size_of::<image::DataDirectory>())
+            .ok_or_else(|| parse::invalid_data("bad SizeOfOptionalHeader"))
+        );
+        try!(io::copy(&mut io::repeat(0).take(trailing as u64), &mut headers));
+        for section in &sections {
+            try!(headers.write_pod(section));
+        }
+    }
+
+    // debug directory entries locate their data by file offset as well as by address
+    if let Some(dir) = directories.get(nt::DirectoryEntry::Debug as usize) {
+        let section = sections.iter().enumerate()
+            .filter(|&(i, s)| i != index && s.contains_virtual_address(dir.virtual_address()))
+            .max_by_key(|&(_, s)| s.virtual_address());
+        if let (false, Some((_, section))) = (dir.is_empty(), section) {
+            let offset = dir.virtual_address() - section.virtual_address();
+            if offset as u64 + dir.size() as u64 > section.size_of_raw_data() as u64 {
+                return Err(parse::invalid_data("debug directory outside of section data"))
+            }
+            let start = section.pointer_to_raw_data() as usize + offset as usize;
+            let count = dir.size() as usize / size_of::<image::DebugDirectory>();
+            let entries = try!(out.get_mut(start..)
+                .ok_or_else(|| parse::invalid_data("debug directory outside of section data"))
+            );
+            for entry in entries.chunks_mut(size_of::<image::DebugDirectory>()).take(count) {
+                let entry: &mut image::DebugDirectory = try!(Pod::try_merge_mut(entry)
+                    .ok_or_else(|| parse::invalid_data("debug directory outside of section data"))
+                );
+                if entry.pointer_to_raw_data() != 0 {
+                    let pointer = moved(entry.pointer_to_raw_data());
+                    entry.pointer_to_raw_data.set(pointer);
+                }
+            }
+        }
+    }
+
+    // CheckSum sits at the same offset in both optional header formats
+    let field = dos.lfanew() as usize + size_of::<u32>() + size_of::<image::FileHeader>() + 0x40;
+    let check_sum = parse::check_sum(&out, field);
+    try!((&mut out[field..]).write_pod(&check_sum.to_le()));
+
+    write.write_all(&out)
+}
+
+#[cfg(test)]
+mod tests {
+    use testing::{ImageBuilder, put_u16, put_u32};
+    use std::io;
+    use traits::{PeHeaders, PeRead};
+    use {nt, parse, View, File};
+    use super::*;
+
+    fn resources<F: Fn(&mut [u8])>(patch: F) -> Vec<u8> {
+        let mut data = vec![0u8; 0x84];
+        put_u16(&mut data, 0x0e, 1);
+        put_u32(&mut data, 0x10, 16);
+        put_u32(&mut data, 0x14, 0x80000018);
+        put_u16(&mut data, 0x24, 1);
+        put_u32(&mut data, 0x28, 0x80000070);
+        put_u32(&mut data, 0x2c, 0x80000030);
+        put_u16(&mut data, 0x3e, 1);
+        put_u32(&mut data, 0x40, 0x409);
+        put_u32(&mut data, 0x44, 0x48);
+        put_u32(&mut data, 0x48, 0x1080);
+        put_u32(&mut data, 0x4c, 4);
+        put_u32(&mut data, 0x50, 1252);
+        put_u16(&mut data, 0x70, 3);
+        data[0x72..0x78].copy_from_slice(b"A\0B\0C\0");
+        data[0x80..0x84].copy_from_slice(b"DATA");
+        patch(&mut data);
+
+        ImageBuilder::new(nt::NtKind::Win64)
+            .directory(nt::DirectoryEntry::Resource, 0x1000, 0x84)
+            .section(0x1000, data)
+            .build()
+    }
+
+    #[test]
+    fn walk() {
+        let image = resources(|_| ());
+        let view = View::new(&image[..]).unwrap();
+        let resources = view.resources().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].kind, nt::ResourceId::Id(16));
+        assert_eq!(resources[0].name, nt::ResourceId::Name("ABC".into()));
+        assert_eq!(resources[0].language, 0x409);
+        assert_eq!(resources[0].data.code_page(), 1252);
+        assert_eq!(view.resource_data(&resources[0]).unwrap(), b"DATA");
+    }
+
+    #[test]
+    fn cycle() {
+        let image = resources(|data| put_u32(data, 0x2c, 0x80000000));
+        let view = View::new(&image[..]).unwrap();
+
+        assert!(view.resources().unwrap().any(|r| r.is_err()));
+    }
+
+    #[test]
+    fn depth() {
+        let image = resources(|data| put_u32(data, 0x44, 0x80000060));
+        let view = View::new(&image[..]).unwrap();
+
+        assert!(view.resources().unwrap().any(|r| r.is_err()));
+    }
+
+    #[test]
+    fn rebuild() {
+        let image = resources(|_| ());
+        let view = View::new(&image[..]).unwrap();
+
+        let mut table = ResourceTable::from_image(&view).unwrap();
+        let version = (nt::ResourceId::Id(16), nt::ResourceId::Name("ABC".into()), 0x409);
+        assert_eq!(table.get(&version.0, &version.1, version.2).unwrap().data, b"DATA");
+        table.insert(nt::ResourceId::Id(3), nt::ResourceId::Id(1), 0, ResourceData {
+            data: b"ICON".to_vec(),
+            code_page: 0,
+        });
+        table.insert(nt::ResourceId::Name("abd".into()), nt::ResourceId::Id(1), 0x407, ResourceData {
+            data: b"named".to_vec(),
+            code_page: 0,
+        });
+        table.insert(nt::ResourceId::Name("ABC".into()), nt::ResourceId::Id(1), 0x407, ResourceData {
+            data: vec![1, 2, 3],
+            code_page: 0,
+        });
+        table.remove(&version.0, &version.1, version.2);
+
+        let mut out = Vec::new();
+        write_resources(io::Cursor::new(&image), &table, &mut out).unwrap();
+        let image = File::new(io::Cursor::new(&out)).unwrap().map_image().unwrap();
+        let view = View::new(&image[..]).unwrap();
+
+        assert_eq!(view.section_headers().len(), 1);
+        assert_eq!(view.nt_headers().size_of_image(), 0x2000);
+        assert_eq!(ResourceTable::from_image(&view).unwrap(), table);
+
+        let kinds: Vec<_> = view.resources().unwrap().map(|r| r.unwrap().kind).collect();
+        assert_eq!(kinds, vec![nt::ResourceId::Name("ABC".into()), nt::ResourceId::Name("abd".into()), nt::ResourceId::Id(3)]);
+    }
+
+    #[test]
+    fn rebuild_in_place() {
+        // .rsrc first, then .rdata holding a debug directory whose data follows it in the file
+        let mut rdata = vec![0u8; 0x104];
+        put_u32(&mut rdata, 0x0c, 2);
+        put_u32(&mut rdata, 0x10, 4);
+        put_u32(&mut rdata, 0x14, 0x2100);
+        put_u32(&mut rdata, 0x18, 0x2100);
+        rdata[0x100..].copy_from_slice(b"RSDS");
+        let mut image = ImageBuilder::new(nt::NtKind::Win64)
+            .directory(nt::DirectoryEntry::Resource, 0x1000, 0x84)
+            .directory(nt::DirectoryEntry::Debug, 0x2000, 0x1c)
+            .section(0x1000, resources(|_| ())[0x1000..0x1084].to_vec())
+            .section(0x2000, rdata)
+            .build();
+        image.extend_from_slice(b"OVERLAY");
+        let view = View::new(&image[..]).unwrap();
+
+        let mut table = ResourceTable::from_image(&view).unwrap();
+        table.insert(nt::ResourceId::Id(10), nt::ResourceId::Id(1), 0, ResourceData {
+            data: vec![0xcc; 0x200],
+            code_page: 0,
+        });
+
+        let mut out = Vec::new();
+        write_resources(io::Cursor::new(&image), &table, &mut out).unwrap();
+        let file = File::new(io::Cursor::new(&out)).unwrap();
+        let sections = file.section_headers();
+
+        assert_eq!(sections.len(), 2);
+        assert_eq!((sections[0].pointer_to_raw_data(), sections[0].size_of_raw_data()), (0x1000, 0x400));
+        assert_eq!(sections[1].pointer_to_raw_data(), 0x2200);
+        assert_eq!(&out[0x2300..0x2304], b"RSDS");
+        assert_eq!(out.len(), image.len() + 0x200);
+        assert!(out.ends_with(b"OVERLAY"));
+        assert_eq!(file.nt_headers().check_sum(), parse::check_sum(&out, 0x98));
+
+        let image = File::new(io::Cursor::new(&out)).unwrap().map_image().unwrap();
+        let view = View::new(&image[..]).unwrap();
+        let debug = view.debug_directories().unwrap().next().unwrap().unwrap();
+        assert_eq!(debug.pointer_to_raw_data(), 0x2300);
+        assert_eq!(ResourceTable::from_image(&view).unwrap(), table);
+    }
+
+    #[test]
+    fn rebuild_bad_layout() {
+        let image = ImageBuilder::new(nt::NtKind::Win64)
+            .directory(nt::DirectoryEntry::Resource, 0x1000, 0x84)
+            .directory(nt::DirectoryEntry::Debug, 0x2100, 0x1c)
+            .section(0x1000, resources(|_| ())[0x1000..0x1084].to_vec())
+            .section(0x2000, vec![0u8; 0x200])
+            .build();
+        let mut table = ResourceTable::from_image(&View::new(&image[..]).unwrap()).unwrap();
+        table.insert(nt::ResourceId::Id(10), nt::ResourceId::Id(1), 0, ResourceData {
+            data: vec![0xcc; 0x200],
+            code_page: 0,
+        });
+
+        // the debug directory sits in section data that the file ends before
+        assert!(write_resources(io::Cursor::new(&image[..0x2080]), &table, &mut Vec::new()).is_err());
+
+        // SizeOfImage and the second section's VirtualAddress, placed where it aligns past 4GiB
+        let mut image = image.clone();
+        put_u32(&mut image, 0x90, 0xfffff800);
+        put_u32(&mut image, 0x17c, 0xfffff000);
+        assert!(write_resources(io::Cursor::new(&image), &table, &mut Vec::new()).is_err());
+    }
+
+    const RELOCATIONS: &'static [u8] = &[0, 0x30, 0, 0, 0x0c, 0, 0, 0, 0x10, 0x30, 0, 0];
+
+    #[test]
+    fn rebuild_appended() {
+        // MSVC puts .reloc after .rsrc, so resources that outgrow their address range move
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::Resource, 0x1000, 0x84)
+            .directory(nt::DirectoryEntry::BaseReloc, 0x2000, RELOCATIONS.len() as u32)
+            .section(0x1000, resources(|_| ())[0x1000..0x1084].to_vec())
+            .section(0x2000, RELOCATIONS.to_vec())
+            .build();
+        let view = View::new(&image[..]).unwrap();
+
+        let mut table = ResourceTable::from_image(&view).unwrap();
+        table.insert(nt::ResourceId::Id(10), nt::ResourceId::Id(1), 0, ResourceData {
+            data: vec![0xcc; 0x1000],
+            code_page: 0,
+        });
+
+        let mut out = Vec::new();
+        write_resources(io::Cursor::new(&image), &table, &mut out).unwrap();
+        let file = File::new(io::Cursor::new(&out)).unwrap();
+        let sections = file.section_headers();
+
+        assert_eq!(sections.len(), 3);
+        assert_eq!((sections[0].pointer_to_raw_data(), sections[0].size_of_raw_data()), (0, 0));
+        assert_eq!(sections[1].pointer_to_raw_data(), 0x1e00);
+        assert_eq!((sections[2].virtual_address(), sections[2].pointer_to_raw_data()), (0x3000, 0x2000));
+        assert_eq!(file.nt_headers().size_of_image(), 0x5000);
+        assert_eq!(out.len(), image.len() - 0x200 + sections[2].size_of_raw_data() as usize);
+
+        let image = File::new(io::Cursor::new(&out)).unwrap().map_image().unwrap();
+        let view = View::new(&image[..]).unwrap();
+        assert_eq!(&image[0x2000..0x200c], RELOCATIONS);
+        assert_eq!(ResourceTable::from_image(&view).unwrap(), table);
+    }
+}
@@ -0,0 +1,102 @@
+use std::io;
+use std::mem::size_of;
+use {nt, image, parse, traits};
+
+fn handler_table<T: traits::PeRead + ?Sized>(view: &T, config: &nt::LoadConfig) -> io::Result<Option<Vec<u32>>> {
+    // /SAFESEH:NO images keep the fields for the security cookie but leave the table zeroed
+    let (table, count) = match (config.se_handler_table(), config.se_handler_count()) {
+        (Some(table), Some(count)) if table != 0 => (table, count),
+        _ => return Ok(None),
+    };
+
+    let mut handlers = Vec::new();
+    if count != 0 {
+        let size = try!(count.checked_mul(size_of::<u32>() as u64)
+            .and_then(|size| if size <= u32::max_value() as u64 { Some(size as u32) } else { None })
+            .ok_or_else(|| parse::invalid_data("bad SE handler count"))
+        );
+        let mut read = try!(parse::va_to_rva(view, table).and_then(|rva| view.segment(rva, size)));
+        for _ in 0..count {
+            handlers.push(try!(parse::read_u32(&mut read)));
+        }
+    }
+
+    // the loader binary searches this table, but don't trust the linker to have sorted it
+    handlers.sort();
+    handlers.dedup();
+
+    Ok(Some(handlers))
+}
+
+pub fn safe_seh<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<nt::SafeSeh> {
+    if view.kind() != nt::NtKind::Win32 {
+        return Err(parse::invalid_data("SafeSEH only applies to 32-bit images"))
+    }
+
+    let handlers = if view.directory_header(nt::DirectoryEntry::LoadConfig).is_some() {
+        try!(view.load_config().and_then(|config| handler_table(view, &config)))
+    } else {
+        None
+    };
+
+    Ok(nt::SafeSeh {
+        no_seh: view.nt_headers().dll_characteristics() & image::DLLCHARACTERISTICS_NO_SEH != 0,
+        handlers: handlers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use testing::{ImageBuilder, put_u32};
+    use traits::PeRead;
+    use {nt, image, View};
+
+    #[test]
+    fn handlers() {
+        let mut config = vec![0u8; 0x48];
+        put_u32(&mut config, 0, 0x48);
+        put_u32(&mut config, 0x40, 0x10003000);
+        put_u32(&mut config, 0x44, 3);
+
+        let mut table = vec![0u8; 0xc];
+        put_u32(&mut table, 0, 0x1040);
+        put_u32(&mut table, 4, 0x1000);
+        put_u32(&mut table, 8, 0x1020);
+
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::LoadConfig, 0x2000, 0x48)
+            .section(0x2000, config)
+            .section(0x3000, table)
+            .build();
+        let view = View::new(&image[..]).unwrap();
+
+        let seh = view.safe_seh().unwrap();
+        assert_eq!(seh.handlers, Some(vec![0x1000, 0x1020, 0x1040]));
+        assert!(seh.compliant() && !seh.no_seh);
+        assert!(seh.is_registered(0x1020) && !seh.is_registered(0x1030));
+
+        let mut config = vec![0u8; 0x48];
+        put_u32(&mut config, 0, 0x48);
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .directory(nt::DirectoryEntry::LoadConfig, 0x2000, 0x48)
+            .section(0x2000, config)
+            .build();
+        let seh = View::new(&image[..]).unwrap().safe_seh().unwrap();
+        assert_eq!(seh.handlers, None);
+        assert!(!seh.compliant());
+
+        let image = ImageBuilder::new(nt::NtKind::Win32).build();
+        let seh = View::new(&image[..]).unwrap().safe_seh().unwrap();
+        assert_eq!(seh.handlers, None);
+        assert!(!seh.compliant());
+
+        let image = ImageBuilder::new(nt::NtKind::Win32)
+            .dll_characteristics(image::DLLCHARACTERISTICS_NO_SEH)
+            .build();
+        let seh = View::new(&image[..]).unwrap().safe_seh().unwrap();
+        assert!(seh.no_seh && seh.compliant() && !seh.is_registered(0x1000));
+
+        let image = ImageBuilder::new(nt::NtKind::Win64).build();
+        assert!(View::new(&image[..]).unwrap().safe_seh().is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::{self, Read};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INDEX2: &'static str = "index2.txt";
+const FILE_PTR: &'static str = "file.ptr";
+
+pub fn symbol_path(name: &str, key: &str) -> PathBuf {
+    Path::new(name).join(key).join(name)
+}
+
+fn file_ptr(root: &Path, path: &Path) -> io::Result<Option<PathBuf>> {
+    let mut ptr = String::new();
+    match fs::File::open(path) {
+        Ok(mut file) => try!(file.read_to_string(&mut ptr)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let ptr = ptr.trim();
+    if ptr.starts_with("MSG:") {
+        return Ok(None)
+    }
+    let ptr = if ptr.starts_with("PATH:") { &ptr[5..] } else { ptr };
+
+    // pointers are usually written on Windows, so also try them relative to the store
+    let ptr = PathBuf::from(ptr);
+    if ptr.is_file() {
+        return Ok(Some(ptr))
+    }
+    let relative = root.join(ptr.to_string_lossy().trim_start_matches(|c| c == '\\' || c == '/').replace('\\', "/"));
+    Ok(if relative.is_file() { Some(relative) } else { None })
+}
+
+pub fn find_symbol<P: AsRef<Path>>(root: P, name: &str, key: &str) -> io::Result<Option<PathBuf>> {
+    let root = root.as_ref();
+
+    let mut directories = Vec::new();
+    if root.join(INDEX2).is_file() {
+        let prefix: String = name.chars().take(2).collect();
+        directories.push(root.join(prefix).join(name).join(key));
+    }
+    directories.push(root.join(name).join(key));
+
+    for directory in directories {
+        let path = directory.join(name);
+        if path.is_file() {
+            return Ok(Some(path))
+        }
+
+        if let Some(path) = try!(file_ptr(root, &directory.join(FILE_PTR))) {
+            return Ok(Some(path))
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+    use std::io::Write;
+    use std::path::Path;
+    use super::*;
+
+    fn write(path: &Path, data: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::File::create(path).unwrap().write_all(data).unwrap();
+    }
+
+    #[test]
+    fn lookup() {
+        let root = env::temp_dir().join(format!("winpe-symstore-{}", process::id()));
+        let two_tier = root.join("two-tier");
+
+        write(&root.join(symbol_path("a.dll", "5A1B2C3D1f000")), b"");
+        write(&root.join("b.pdb/0123456789ABCDEF0123456789ABCDEF1/file.ptr"), b"PATH:stored\\b.pdb\r\n");
+        write(&root.join("stored/b.pdb"), b"");
+        write(&root.join("c.pdb/0123456789ABCDEF0123456789ABCDEF1/file.ptr"), b"MSG: file not available");
+        write(&two_tier.join("index2.txt"), b"");
+        write(&two_tier.join("a.").join(symbol_path("a.dll", "5A1B2C3D1f000")), b"");
+
+        assert_eq!(find_symbol(&root, "a.dll", "5A1B2C3D1f000").unwrap(), Some(root.join("a.dll/5A1B2C3D1f000/a.dll")));
+        assert_eq!(find_symbol(&root, "a.dll", "5A1B2C3D20000").unwrap(), None);
+        assert_eq!(find_symbol(&root, "b.pdb", "0123456789ABCDEF0123456789ABCDEF1").unwrap(), Some(root.join("stored/b.pdb")));
+        assert_eq!(find_symbol(&root, "c.pdb", "0123456789ABCDEF0123456789ABCDEF1").unwrap(), None);
+        assert_eq!(find_symbol(&two_tier, "a.dll", "5A1B2C3D1f000").unwrap(), Some(two_tier.join("a./a.dll/5A1B2C3D1f000/a.dll")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use std::mem::size_of;
+use pod::Pod;
+use {image, nt};
+
+const HEADERS_SIZE: u32 = 0x400;
+const SECTION_ALIGNMENT: u32 = 0x1000;
+const FILE_ALIGNMENT: u32 = 0x200;
+
+pub struct ImageBuilder {
+    kind: nt::NtKind,
+    machine: u16,
+    image_base: u64,
+    dll_characteristics: u16,
+    time_date_stamp: u32,
+    directories: [(u32, u32); image::NUMBEROF_DIRECTORY_ENTRIES],
+    sections: Vec<(u32, Vec<u8>)>,
+}
+
+impl ImageBuilder {
+    pub fn new(kind: nt::NtKind) -> Self {
+        ImageBuilder {
+            kind: kind,
+            machine: match kind {
+                nt::NtKind::Win32 => image::FILE_MACHINE_I386,
+                nt::NtKind::Win64 => image::FILE_MACHINE_AMD64,
+            },
+            image_base: 0x10000000,
+            dll_characteristics: 0,
+            time_date_stamp: 0,
+            directories: [(0, 0); image::NUMBEROF_DIRECTORY_ENTRIES],
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn machine(mut self, machine: u16) -> Self {
+        self.machine = machine;
+        self
+    }
+
+    pub fn dll_characteristics(mut self, characteristics: u16) -> Self {
+        self.dll_characteristics = characteristics;
+        self
+    }
+
+    pub fn time_date_stamp(mut self, time_date_stamp: u32) -> Self {
+        self.time_date_stamp = time_date_stamp;
+        self
+    }
+
+    pub fn directory(mut self, entry: nt::DirectoryEntry, address: u32, size: u32) -> Self {
+        self.directories[entry as usize] = (address, size);
+        self
+    }
+
+    pub fn section(mut self, address: u32, data: Vec<u8>) -> Self {
+        self.sections.push((address, data));
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let size_of_image = self.sections.iter()
+            .map(|&(address, ref data)| address + (data.len() as u32 + SECTION_ALIGNMENT - 1) / SECTION_ALIGNMENT * SECTION_ALIGNMENT)
+            .max().unwrap_or(SECTION_ALIGNMENT);
+        let mut out = vec![0u8; size_of_image as usize];
+
+        let lfanew = size_of::<image::DosHeader>();
+        let mut dos: image::DosHeader = Pod::zeroed();
+        dos.magic.set(image::DOS_SIGNATURE);
+        dos.lfanew.set(lfanew as u32);
+        out[..lfanew].copy_from_slice(dos.as_bytes());
+
+        let mut offset = lfanew;
+        match self.kind {
+            nt::NtKind::Win32 => {
+                let mut nt: image::NtHeaders32 = Pod::zeroed();
+                self.file_header(&mut nt.file_header);
+                nt.signature.set(image::NT_SIGNATURE);
+                let optional = &mut nt.optional_header;
+                optional.magic.set(image::NT_OPTIONAL_HDR32_MAGIC);
+                optional.image_base.set(self.image_base as u32);
+                self.optional_header(optional, size_of_image);
+                out[offset..offset + size_of::<image::NtHeaders32>()].copy_from_slice(nt.as_bytes());
+                offset += size_of::<image::NtHeaders32>();
+            },
+            nt::NtKind::Win64 => {
+                let mut nt: image::NtHeaders64 = Pod::zeroed();
+                self.file_header(&mut nt.file_header);
+                nt.signature.set(image::NT_SIGNATURE);
+                let optional = &mut nt.optional_header;
+                optional.magic.set(image::NT_OPTIONAL_HDR64_MAGIC);
+                optional.base_of_data.set(self.image_base as u32);
+                optional.image_base.set((self.image_base >> 32) as u32);
+                self.optional_header(optional, size_of_image);
+                out[offset..offset + size_of::<image::NtHeaders64>()].copy_from_slice(nt.as_bytes());
+                offset += size_of::<image::NtHeaders64>();
+            },
+        }
+
+        for &(address, size) in &self.directories {
+            let mut directory: image::DataDirectory = Pod::zeroed();
+            directory.virtual_address.set(address);
+            directory.size.set(size);
+            out[offset..offset + size_of::<image::DataDirectory>()].copy_from_slice(directory.as_bytes());
+            offset += size_of::<image::DataDirectory>();
+        }
+
+        for &(address, ref data) in &self.sections {
+            let mut section: image::SectionHeader = Pod::zeroed();
+            section.virtual_size_physical_address.set(data.len() as u32);
+            section.virtual_address.set(address);
+            section.size_of_raw_data.set((data.len() as u32 + FILE_ALIGNMENT - 1) / FILE_ALIGNMENT * FILE_ALIGNMENT);
+            section.pointer_to_raw_data.set(address);
+            section.characteristics.set(image::SCN_MEM_READ);
+            out[offset..offset + size_of::<image::SectionHeader>()].copy_from_slice(section.as_bytes());
+            offset += size_of::<image::SectionHeader>();
+
+            out[address as usize..address as usize + data.len()].copy_from_slice(data);
+        }
+
+        out
+    }
+
+    fn file_header(&self, header: &mut image::FileHeader) {
+        header.machine.set(self.machine);
+        header.number_of_sections.set(self.sections.len() as u16);
+        header.time_date_stamp.set(self.time_date_stamp);
+        header.size_of_optional_header.set((self.kind.size_of_optional_header() + image::NUMBEROF_DIRECTORY_ENTRIES * size_of::<image::DataDirectory>()) as u16);
+        header.characteristics.set(image::FILE_EXECUTABLE_IMAGE | image::FILE_DLL);
+    }
+
+    fn optional_header<T: ::byteorder_pod::EndianConvert>(&self, header: &mut image::OptionalHeader<T>, size_of_image: u32) {
+        header.section_alignment.set(SECTION_ALIGNMENT);
+        header.file_alignment.set(FILE_ALIGNMENT);
+        header.size_of_image.set(size_of_image);
+        header.size_of_headers.set(HEADERS_SIZE);
+        header.dll_characteristics.set(self.dll_characteristics);
+        header.number_of_rva_and_sizes.set(image::NUMBEROF_DIRECTORY_ENTRIES as u32);
+    }
+}
+
+pub fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&[value as u8, (value >> 8) as u8]);
+}
+
+pub fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+    put_u16(data, offset, value as u16);
+    put_u16(data, offset + 2, (value >> 16) as u16);
+}
+
+pub enum ExportTarget<'a> {
+    Rva(u32),
+    Forwarder(&'a str),
+}
+
+// lays out an export directory at address, with ordinals assigned from 1 in the given order
+// and empty names left out of the name table
+pub fn export_directory(address: u32, module: &str, exports: &[(&str, ExportTarget)]) -> Vec<u8> {
+    let count = exports.len();
+    let functions = size_of::<image::ExportDirectory>();
+    let names = functions + count * 4;
+    let ordinals = names + count * 4;
+    let mut data = vec![0u8; ordinals + count * 2];
+
+    let string = |data: &mut Vec<u8>, value: &str| {
+        let offset = data.len();
+        data.extend(value.as_bytes());
+        data.push(0);
+        address + offset as u32
+    };
+
+    let mut sorted = exports.iter().enumerate()
+        .filter(|&(_, &(name, _))| !name.is_empty())
+        .map(|(ordinal, &(name, _))| (name, ordinal))
+        .collect::<Vec<_>>();
+    sorted.sort();
+
+    let mut directory: image::ExportDirectory = Pod::zeroed();
+    directory.name.set(string(&mut data, module));
+    directory.base.set(1);
+    directory.number_of_functions.set(count as u32);
+    directory.number_of_names.set(sorted.len() as u32);
+    directory.address_of_functions.set(address + functions as u32);
+    directory.address_of_names.set(address + names as u32);
+    directory.address_of_name_ordinals.set(address + ordinals as u32);
+    data[..functions].copy_from_slice(directory.as_bytes());
+
+    for (ordinal, &(_, ref target)) in exports.iter().enumerate() {
+        let rva = match *target {
+            ExportTarget::Rva(rva) => rva,
+            ExportTarget::Forwarder(forwarder) => string(&mut data, forwarder),
+        };
+        put_u32(&mut data, functions + ordinal * 4, rva);
+    }
+
+    for (index, &(name, ordinal)) in sorted.iter().enumerate() {
+        let rva = string(&mut data, name);
+        put_u32(&mut data, names + index * 4, rva);
+        put_u16(&mut data, ordinals + index * 2, ordinal as u16);
+    }
+
+    data
+}
@@ -1,6 +1,7 @@
 use std::io;
-use std::ffi::CString;
-use {nt, image, parse};
+use std::ffi::{CStr, CString};
+use std::mem::size_of;
+use {nt, image, parse, bind, debug, guard, hash, resource, seh, unwind, version};
 
 pub trait PeHeaders {
     fn kind(&self) -> nt::NtKind;
@@ -78,6 +79,21 @@ pub trait PeRead: PeHeaders {
             })
     }
 
+    fn headers_segment(&self, rva: u32, size: u32) -> io::Result<Self::Read> {
+        use pod::Pod;
+
+        let size_of_headers = self.nt_headers().size_of_headers();
+        if rva.checked_add(size).map(|end| end > size_of_headers).unwrap_or(true) {
+            return Err(parse::invalid_data("segment reaches beyond headers end"))
+        }
+
+        // headers are mapped at the image base as if they were a section of their own
+        let mut headers: image::SectionHeader = Pod::zeroed();
+        headers.virtual_size_physical_address.set(size_of_headers);
+        headers.size_of_raw_data.set(size_of_headers);
+        self.section_segment(&headers, rva, size)
+    }
+
     fn section(&self, section: &image::SectionHeader) -> io::Result<Self::Read> {
         self.section_segment(section, 0, section.virtual_size())
     }
@@ -100,15 +116,196 @@ pub trait PeRead: PeHeaders {
             .map(parse::ImportIterator::new)
     }
 
+    fn bound_imports(&self) -> io::Result<bind::BoundImportIterator> {
+        bind::bound_imports(self)
+    }
+
+    fn imphash(&self) -> io::Result<Option<String>> {
+        hash::imphash(self)
+    }
+
+    fn delay_imports(&self) -> io::Result<parse::DelayImportIterator<Self::Read, &Self>> {
+        self.directory_header(nt::DirectoryEntry::DelayImport)
+            .ok_or_else(|| parse::invalid_data("delay import segment not found"))
+            .and_then(|data| self.directory(data))
+            .map(|data| parse::DelayImportIterator::new(data, self))
+    }
+
+    fn export_directory(&self) -> io::Result<image::ExportDirectory> {
+        parse::export_directory(self).map(|(directory, _)| directory)
+    }
+
+    fn exports(&self) -> io::Result<parse::ExportIterator<Self::Read, &Self>> {
+        let (directory, data) = try!(parse::export_directory(self));
+
+        directory.number_of_functions().checked_mul(size_of::<u32>() as u32)
+            .ok_or_else(|| parse::invalid_data("bad export function count"))
+            .and_then(|size| self.segment(directory.address_of_functions(), size))
+            .and_then(|functions| parse::ExportIterator::new(functions, self, directory, data))
+    }
+
+    fn export_by_name(&self, name: &CStr, hint: Option<u16>) -> io::Result<Option<nt::Export>> {
+        parse::export_directory(self)
+            .and_then(|(directory, data)| parse::find_export_by_name(self, &directory, &data, name, hint))
+    }
+
+    fn export_by_ordinal(&self, ordinal: u16) -> io::Result<Option<nt::Export>> {
+        parse::export_directory(self)
+            .and_then(|(directory, data)| parse::find_export_by_ordinal(self, &directory, &data, ordinal))
+    }
+
+    fn find_export(&self, symbol: &nt::ImportSymbol) -> io::Result<Option<nt::Export>> {
+        match *symbol {
+            nt::ImportSymbol::Ordinal(ordinal) => self.export_by_ordinal(ordinal),
+            nt::ImportSymbol::Name { ordinal_hint, ref name } => self.export_by_name(name, Some(ordinal_hint)),
+        }
+    }
+
+    fn exphash(&self) -> io::Result<Option<String>> {
+        hash::exphash(self)
+    }
+
+    fn resources(&self) -> io::Result<resource::ResourceIterator<&Self>> {
+        self.directory_header(nt::DirectoryEntry::Resource)
+            .ok_or_else(|| parse::invalid_data("resource segment not found"))
+            .and_then(|&data| resource::ResourceIterator::new(self, data))
+    }
+
+    fn resource_data(&self, resource: &nt::Resource) -> io::Result<Self::Read> {
+        self.segment(resource.data.offset_to_data(), resource.data.size())
+    }
+
+    fn version_info(&self) -> io::Result<nt::VersionInfo> {
+        use std::io::Read;
+
+        let resource = try!(try!(self.resources())
+            .filter(|resource| resource.as_ref().map(|r| r.kind == nt::ResourceId::Id(image::RT_VERSION)).unwrap_or(true))
+            .next()
+            .unwrap_or_else(|| Err(parse::invalid_data("version resource not found")))
+        );
+
+        let mut data = Vec::new();
+        try!(self.resource_data(&resource).and_then(|mut r| r.read_to_end(&mut data)));
+
+        version::parse_version_info(&data)
+    }
+
+    fn debug_directories(&self) -> io::Result<debug::DebugIterator<Self::Read>> {
+        self.directory_header(nt::DirectoryEntry::Debug)
+            .ok_or_else(|| parse::invalid_data("debug segment not found"))
+            .and_then(|data| self.directory(data))
+            .map(debug::DebugIterator::new)
+    }
+
+    fn debug_data(&self, debug: &image::DebugDirectory) -> io::Result<Self::Read> {
+        if debug.address_of_raw_data() == 0 {
+            return Err(parse::invalid_data("debug data not mapped"))
+        }
+
+        self.segment(debug.address_of_raw_data(), debug.size_of_data())
+    }
+
+    fn debug_codeview(&self, debug: &image::DebugDirectory) -> io::Result<nt::CodeView> {
+        use std::io::Read;
+
+        let mut data = Vec::new();
+        try!(self.debug_data(debug).and_then(|mut r| r.read_to_end(&mut data)));
+
+        debug::parse_codeview(&data)
+    }
+
+    fn codeview(&self) -> io::Result<Option<nt::CodeView>> {
+        if self.directory_header(nt::DirectoryEntry::Debug).is_none() {
+            return Ok(None)
+        }
+
+        for debug in try!(self.debug_directories()) {
+            let debug = try!(debug);
+            if debug.kind() == image::DEBUG_TYPE_CODEVIEW {
+                return self.debug_codeview(&debug).map(Some)
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn tls(&self) -> io::Result<nt::Tls> {
+        parse::tls(self)
+    }
+
+    fn load_config(&self) -> io::Result<nt::LoadConfig> {
+        parse::load_config(self)
+    }
+
+    fn safe_seh(&self) -> io::Result<nt::SafeSeh> {
+        seh::safe_seh(self)
+    }
+
+    fn guard_cf_functions(&self) -> io::Result<guard::GuardTableIterator<Self::Read>> {
+        let config = try!(self.load_config());
+        guard::guard_table(self, &config, config.guard_cf_function_table(), config.guard_cf_function_count())
+    }
+
+    fn guard_iat_entries(&self) -> io::Result<guard::GuardTableIterator<Self::Read>> {
+        let config = try!(self.load_config());
+        guard::guard_table(self, &config, config.guard_address_taken_iat_entry_table(), config.guard_address_taken_iat_entry_count())
+    }
+
+    fn guard_long_jump_targets(&self) -> io::Result<guard::GuardTableIterator<Self::Read>> {
+        let config = try!(self.load_config());
+        guard::guard_table(self, &config, config.guard_long_jump_target_table(), config.guard_long_jump_target_count())
+    }
+
+    fn guard_eh_continuations(&self) -> io::Result<guard::GuardTableIterator<Self::Read>> {
+        let config = try!(self.load_config());
+        guard::guard_table(self, &config, config.guard_eh_continuation_table(), config.guard_eh_continuation_count())
+    }
+
+    fn runtime_functions(&self) -> io::Result<unwind::RuntimeFunctionIterator<Self::Read>> {
+        unwind::exception_directory(self)
+            .and_then(|data| self.directory(&data))
+            .map(unwind::RuntimeFunctionIterator::new)
+    }
+
+    fn runtime_function(&self, rva: u32) -> io::Result<Option<image::RuntimeFunction>> {
+        unwind::find_runtime_function(self, rva)
+    }
+
+    fn unwind_info(&self, function: &image::RuntimeFunction) -> io::Result<nt::UnwindInfo> {
+        unwind::parse_unwind_info(self, function)
+    }
+
+    fn function_entries(&self) -> io::Result<unwind::FunctionEntryIterator<Self::Read>> {
+        unwind::function_entries(self)
+    }
+
+    fn function_unwind(&self, function: &nt::FunctionEntry) -> io::Result<nt::FunctionUnwind> {
+        unwind::function_unwind(self, function)
+    }
+
+    fn virtual_unwind<F: FnMut(u64) -> io::Result<u64>>(&self, base: u64, context: &nt::Context, read: F) -> io::Result<nt::Context> {
+        unwind::virtual_unwind(self, base, context, read)
+    }
+
     fn import_table(&self, desc: &image::ImportDescriptor) -> io::Result<parse::ImportTableIterator<Self::Read, &Self>> {
-        let address = desc.original_first_thunk();
-        let address = if address == 0 {
-            desc.first_thunk()
-        } else {
-            address
+        let names = match desc.original_first_thunk() {
+            0 => None,
+            address => Some(address),
         };
 
-        self.segment_from(address).map(|data| parse::ImportTableIterator::new(data, self))
+        self.segment_from(names.unwrap_or(desc.first_thunk()))
+            .map(|data| parse::ImportTableIterator::new(data, self, names, desc.first_thunk()))
+    }
+
+    fn delay_import_table(&self, desc: &image::DelayLoadDescriptor) -> io::Result<parse::ImportTableIterator<Self::Read, &Self>> {
+        let address = desc.import_name_table_rva();
+        if address == 0 {
+            return Err(parse::invalid_data("delay import name table not found"))
+        }
+
+        let base = if desc.rva_based() { 0 } else { self.nt_headers().image_base() };
+        self.segment_from(address)
+            .map(|data| parse::ImportTableIterator::with_base(data, self, Some(address), desc.import_address_table_rva(), base))
     }
 }
 
@@ -116,6 +313,20 @@ pub trait PeWrite<'a>: PeHeaders {
     type Write: io::Write + 'a;
 
     fn write_section(&'a mut self, section: &image::SectionHeader, offset: u32) -> io::Result<Self::Write>;
+
+    fn write_headers(&'a mut self, offset: u32) -> io::Result<Self::Write> {
+        use pod::Pod;
+
+        let size_of_headers = self.nt_headers().size_of_headers();
+        if offset > size_of_headers {
+            return Err(parse::invalid_data("offset beyond headers end"))
+        }
+
+        let mut headers: image::SectionHeader = Pod::zeroed();
+        headers.virtual_size_physical_address.set(size_of_headers);
+        headers.size_of_raw_data.set(size_of_headers);
+        self.write_section(&headers, offset)
+    }
 }
 
 impl<'a, T: PeRead + ?Sized> PeRead for &'a T {
@@ -0,0 +1,620 @@
+use std::io::{self, Read};
+use std::mem::size_of;
+use pod::PodReadExt;
+use result::OptionResultExt;
+use {arm, nt, image, parse, traits};
+
+pub struct RuntimeFunctionIterator<R> {
+    fuse: bool,
+    read: R,
+}
+
+impl<R> RuntimeFunctionIterator<R> {
+    pub fn new(functions: R) -> Self {
+        RuntimeFunctionIterator {
+            fuse: false,
+            read: functions,
+        }
+    }
+}
+
+impl<R: Read> RuntimeFunctionIterator<R> {
+    fn try_next(&mut self) -> io::Result<Option<image::RuntimeFunction>> {
+        match try!(self.read.read_pod_or_none::<image::RuntimeFunction>()) {
+            Some(function) if function.begin_address() == 0 && function.end_address() == 0 => Ok(None),
+            function => Ok(function),
+        }
+    }
+}
+
+impl<R: Read> Iterator for RuntimeFunctionIterator<R> {
+    type Item = io::Result<image::RuntimeFunction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+pub struct FunctionEntryIterator<R> {
+    fuse: bool,
+    machine: u16,
+    read: R,
+}
+
+impl<R> FunctionEntryIterator<R> {
+    pub fn new(functions: R, machine: u16) -> Self {
+        FunctionEntryIterator {
+            fuse: false,
+            machine: machine,
+            read: functions,
+        }
+    }
+}
+
+impl<R: Read> FunctionEntryIterator<R> {
+    fn try_next(&mut self) -> io::Result<Option<nt::FunctionEntry>> {
+        if self.machine == image::FILE_MACHINE_AMD64 {
+            return RuntimeFunctionIterator::new(&mut self.read).try_next()
+                .map(|function| function.map(nt::FunctionEntry::Amd64))
+        }
+
+        match try!(self.read.read_pod_or_none::<image::ArmRuntimeFunction>()) {
+            Some(function) if function.begin_address() == 0 && function.unwind_data() == 0 => Ok(None),
+            Some(function) => Ok(Some(match self.machine {
+                image::FILE_MACHINE_ARM64 => nt::FunctionEntry::Arm64(function),
+                _ => nt::FunctionEntry::Arm(function),
+            })),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<R: Read> Iterator for FunctionEntryIterator<R> {
+    type Item = io::Result<nt::FunctionEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fuse {
+            return None
+        }
+
+        let res = self.try_next();
+        if res.as_ref().map(Option::is_none).unwrap_or(true) {
+            self.fuse = true
+        }
+        res.invert()
+    }
+}
+
+fn exception_data<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<image::DataDirectory> {
+    view.directory_header(nt::DirectoryEntry::Exception)
+        .cloned()
+        .ok_or_else(|| parse::invalid_data("exception segment not found"))
+}
+
+pub fn exception_directory<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<image::DataDirectory> {
+    if view.nt_headers().file_header().machine() != image::FILE_MACHINE_AMD64 {
+        return Err(parse::invalid_data("exception directory is only supported for AMD64"))
+    }
+
+    exception_data(view)
+}
+
+pub fn function_entries<T: traits::PeRead + ?Sized>(view: &T) -> io::Result<FunctionEntryIterator<T::Read>> {
+    let machine = view.nt_headers().file_header().machine();
+    match machine {
+        image::FILE_MACHINE_AMD64 | image::FILE_MACHINE_ARM64 | image::FILE_MACHINE_ARMNT => (),
+        _ => return Err(parse::invalid_data("unsupported machine for exception directory")),
+    }
+
+    exception_data(view)
+        .and_then(|data| view.directory(&data))
+        .map(|read| FunctionEntryIterator::new(read, machine))
+}
+
+pub fn function_unwind<T: traits::PeRead + ?Sized>(view: &T, function: &nt::FunctionEntry) -> io::Result<nt::FunctionUnwind> {
+    match *function {
+        nt::FunctionEntry::Amd64(ref function) => parse_unwind_info(view, function).map(nt::FunctionUnwind::Amd64),
+        nt::FunctionEntry::Arm64(ref function) => arm::parse_arm64_unwind(view, function).map(nt::FunctionUnwind::Arm64),
+        nt::FunctionEntry::Arm(ref function) => arm::parse_arm_unwind(view, function).map(nt::FunctionUnwind::Arm),
+    }
+}
+
+fn runtime_function<T: traits::PeRead + ?Sized>(view: &T, address: u32) -> io::Result<image::RuntimeFunction> {
+    view.segment(address, size_of::<image::RuntimeFunction>() as u32)
+        .and_then(|mut read| read.read_pod())
+}
+
+pub fn find_runtime_function<T: traits::PeRead + ?Sized>(view: &T, rva: u32) -> io::Result<Option<image::RuntimeFunction>> {
+    let data = try!(exception_directory(view));
+    let entry_size = size_of::<image::RuntimeFunction>() as u32;
+
+    // .pdata is sorted by begin address so the loader can binary search it too
+    let (mut low, mut high) = (0, data.size() / entry_size);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let function = try!(runtime_function(view, data.virtual_address() + mid * entry_size));
+
+        if rva < function.begin_address() {
+            high = mid;
+        } else if rva >= function.end_address() {
+            low = mid + 1;
+        } else {
+            return Ok(Some(function))
+        }
+    }
+
+    Ok(None)
+}
+
+fn unwind_code(codes: &[image::UnwindCode], index: usize) -> io::Result<u16> {
+    codes.get(index)
+        .map(image::UnwindCode::frame_offset)
+        .ok_or_else(|| parse::invalid_data("unwind code truncated"))
+}
+
+fn unwind_codes(version: u8, codes: &[image::UnwindCode]) -> io::Result<Vec<nt::UnwindCode>> {
+    let mut out = Vec::new();
+    let mut index = 0;
+
+    while index < codes.len() {
+        let code = codes[index];
+        let info = code.op_info();
+        let (op, slots) = match code.unwind_op() {
+            image::UWOP_PUSH_NONVOL => (nt::UnwindOp::PushNonvol(info), 1),
+            image::UWOP_ALLOC_LARGE if info == 0 =>
+                (nt::UnwindOp::AllocLarge(try!(unwind_code(codes, index + 1)) as u32 * 8), 2),
+            image::UWOP_ALLOC_LARGE if info == 1 => {
+                let size = try!(unwind_code(codes, index + 1)) as u32 | (try!(unwind_code(codes, index + 2)) as u32) << 16;
+                (nt::UnwindOp::AllocLarge(size), 3)
+            },
+            image::UWOP_ALLOC_SMALL => (nt::UnwindOp::AllocSmall(info as u32 * 8 + 8), 1),
+            image::UWOP_SET_FPREG => (nt::UnwindOp::SetFpreg, 1),
+            image::UWOP_SAVE_NONVOL => (nt::UnwindOp::SaveNonvol {
+                register: info,
+                offset: try!(unwind_code(codes, index + 1)) as u32 * 8,
+            }, 2),
+            image::UWOP_SAVE_NONVOL_FAR => (nt::UnwindOp::SaveNonvol {
+                register: info,
+                offset: try!(unwind_code(codes, index + 1)) as u32 | (try!(unwind_code(codes, index + 2)) as u32) << 16,
+            }, 3),
+            image::UWOP_EPILOG if version >= 2 => (nt::UnwindOp::Epilog(info), 1),
+            image::UWOP_SAVE_XMM128 => (nt::UnwindOp::SaveXmm128 {
+                register: info,
+                offset: try!(unwind_code(codes, index + 1)) as u32 * 16,
+            }, 2),
+            image::UWOP_SAVE_XMM128_FAR => (nt::UnwindOp::SaveXmm128 {
+                register: info,
+                offset: try!(unwind_code(codes, index + 1)) as u32 | (try!(unwind_code(codes, index + 2)) as u32) << 16,
+            }, 3),
+            image::UWOP_PUSH_MACHFRAME if info <= 1 => (nt::UnwindOp::PushMachframe(info == 1), 1),
+            _ => return Err(parse::invalid_data("bad unwind code")),
+        };
+
+        out.push(nt::UnwindCode {
+            offset: code.code_offset(),
+            op: op,
+        });
+        index += slots;
+    }
+
+    Ok(out)
+}
+
+pub fn parse_unwind_info<T: traits::PeRead + ?Sized>(view: &T, function: &image::RuntimeFunction) -> io::Result<nt::UnwindInfo> {
+    let mut address = function.unwind_info_address();
+
+    // the low bit marks an entry that shares the unwind data of another RUNTIME_FUNCTION
+    if address & 1 != 0 {
+        address = try!(runtime_function(view, address & !1)).unwind_info_address();
+        if address & 1 != 0 {
+            return Err(parse::invalid_data("nested indirect runtime function"))
+        }
+    }
+
+    let mut read = try!(view.segment_from(address));
+    let header: image::UnwindInfo = try!(read.read_pod());
+    if header.version() != 1 && header.version() != 2 {
+        return Err(parse::invalid_data("bad unwind info version"))
+    }
+
+    // the code array is padded to an even number of slots
+    let slots = (header.count_of_codes() as usize + 1) & !1;
+    let mut codes = Vec::with_capacity(slots);
+    for _ in 0..slots {
+        codes.push(try!(read.read_pod::<image::UnwindCode>()));
+    }
+    codes.truncate(header.count_of_codes() as usize);
+
+    let trailer = address + (size_of::<image::UnwindInfo>() + slots * size_of::<image::UnwindCode>()) as u32;
+    let flags = header.flags();
+    let (handler, chained) = if flags & image::UNW_FLAG_CHAININFO != 0 {
+        (None, Some(try!(read.read_pod::<image::RuntimeFunction>())))
+    } else if flags & (image::UNW_FLAG_EHANDLER | image::UNW_FLAG_UHANDLER) != 0 {
+        (Some(nt::UnwindHandler {
+            address: try!(parse::read_u32(&mut read)),
+            data: trailer + size_of::<u32>() as u32,
+        }), None)
+    } else {
+        (None, None)
+    };
+
+    Ok(nt::UnwindInfo {
+        version: header.version(),
+        flags: flags,
+        size_of_prolog: header.size_of_prolog(),
+        frame_register: header.frame_register(),
+        frame_offset: header.frame_offset() as u32 * 16,
+        codes: try!(unwind_codes(header.version(), &codes)),
+        handler: handler,
+        chained: chained,
+    })
+}
+
+const UNWIND_CHAIN_DEPTH: usize = 32;
+const EPILOG_SCAN_SIZE: u64 = 64;
+
+fn read_stack<F: FnMut(u64) -> io::Result<u64>>(read: &mut F, context: &mut nt::Context) -> io::Result<u64> {
+    let rsp = context.rsp();
+    let value = try!(read(rsp));
+    context.set_register(nt::Register::Rsp, rsp.wrapping_add(8));
+    Ok(value)
+}
+
+fn code_i32(code: &[u8], offset: usize) -> Option<i32> {
+    code.get(offset..offset + 4)
+        .map(|c| (c[0] as u32 | (c[1] as u32) << 8 | (c[2] as u32) << 16 | (c[3] as u32) << 24) as i32)
+}
+
+// Recognises the restricted epilog forms the compiler is required to emit: an optional
+// `add rsp, N` or `lea rsp, [fp + N]`, a run of `pop reg`, then a return or tail jump.
+fn epilog_unwind<F: FnMut(u64) -> io::Result<u64>>(code: &[u8], rva: u32, function: &image::RuntimeFunction, frame_register: u8, context: &nt::Context, read: &mut F) -> io::Result<Option<nt::Context>> {
+    let byte = |offset: usize| code.get(offset).cloned();
+    let mut rsp = context.rsp();
+    let mut offset = 0;
+
+    match (byte(0), byte(1), byte(2)) {
+        (Some(0x48), Some(0x83), Some(0xc4)) => {
+            match byte(3) {
+                Some(imm) => rsp = rsp.wrapping_add(imm as i8 as u64),
+                None => return Ok(None),
+            }
+            offset = 4;
+        },
+        (Some(0x48), Some(0x81), Some(0xc4)) => {
+            match code_i32(code, 3) {
+                Some(imm) => rsp = rsp.wrapping_add(imm as u64),
+                None => return Ok(None),
+            }
+            offset = 7;
+        },
+        (Some(rex @ 0x48), Some(0x8d), Some(modrm)) | (Some(rex @ 0x49), Some(0x8d), Some(modrm)) => {
+            let register = (modrm & 7) | (rex & 1) << 3;
+            if frame_register == 0 || register != frame_register || (modrm >> 3) & 7 != 4 || modrm & 7 == 4 {
+                return Ok(None)
+            }
+
+            let base = context.registers[register as usize];
+            let (disp, len) = match modrm >> 6 {
+                1 => match byte(3) {
+                    Some(disp) => (disp as i8 as i32, 4),
+                    None => return Ok(None),
+                },
+                2 => match code_i32(code, 3) {
+                    Some(disp) => (disp, 7),
+                    None => return Ok(None),
+                },
+                _ => return Ok(None),
+            };
+            rsp = base.wrapping_add(disp as u64);
+            offset = len;
+        },
+        _ => (),
+    }
+
+    let mut pops = Vec::new();
+    loop {
+        match (byte(offset), byte(offset + 1)) {
+            (Some(op @ 0x58..=0x5f), _) => {
+                pops.push(op - 0x58);
+                offset += 1;
+            },
+            (Some(0x41), Some(op @ 0x58..=0x5f)) => {
+                pops.push(op - 0x58 + 8);
+                offset += 2;
+            },
+            _ => break,
+        }
+    }
+
+    let terminator = match (byte(offset), byte(offset + 1), byte(offset + 2)) {
+        (Some(0xc3), _, _) | (Some(0xf3), Some(0xc3), _) => true,
+        (Some(0xff), Some(0x25), _) | (Some(0x48), Some(0xff), Some(0x25)) => true,
+        (Some(0xe9), _, _) => match code_i32(code, offset + 1) {
+            Some(disp) => {
+                let target = (rva as i64 + offset as i64 + 5 + disp as i64) as u32;
+                target < function.begin_address() || target >= function.end_address()
+            },
+            None => false,
+        },
+        _ => false,
+    };
+    if !terminator {
+        return Ok(None)
+    }
+
+    let mut context = *context;
+    context.set_register(nt::Register::Rsp, rsp);
+    for register in pops {
+        context.registers[register as usize] = try!(read_stack(read, &mut context));
+    }
+    context.rip = try!(read_stack(read, &mut context));
+
+    Ok(Some(context))
+}
+
+pub fn virtual_unwind<T: traits::PeRead + ?Sized, F: FnMut(u64) -> io::Result<u64>>(view: &T, base: u64, context: &nt::Context, mut read: F) -> io::Result<nt::Context> {
+    let rva = try!(context.rip.checked_sub(base)
+        .and_then(|rva| if rva < view.nt_headers().size_of_image() as u64 { Some(rva as u32) } else { None })
+        .ok_or_else(|| parse::invalid_data("instruction pointer outside of image"))
+    );
+
+    let mut context = *context;
+    let mut function = match try!(find_runtime_function(view, rva)) {
+        Some(function) => function,
+        None => {
+            // leaf functions have no unwind data and leave rsp pointing at the return address
+            context.rip = try!(read_stack(&mut read, &mut context));
+            return Ok(context)
+        },
+    };
+
+    let mut info = try!(parse_unwind_info(view, &function));
+    let prolog_offset = rva - function.begin_address();
+
+    if prolog_offset >= info.size_of_prolog as u32 {
+        let mut code = Vec::new();
+        try!(view.segment_from(rva).and_then(|r| r.take(EPILOG_SCAN_SIZE).read_to_end(&mut code)));
+        if let Some(context) = try!(epilog_unwind(&code, rva, &function, info.frame_register, &context, &mut read)) {
+            return Ok(context)
+        }
+    }
+
+    // offsets in SAVE_NONVOL and SAVE_XMM128 are relative to the establisher frame
+    let frame_set = info.frame_register != 0 && info.codes.iter()
+        .any(|code| code.op == nt::UnwindOp::SetFpreg && (prolog_offset >= info.size_of_prolog as u32 || code.offset as u32 <= prolog_offset));
+    let frame = if frame_set {
+        context.registers[info.frame_register as usize].wrapping_sub(info.frame_offset as u64)
+    } else {
+        context.rsp()
+    };
+
+    let mut machine_frame = false;
+    let mut depth = 0;
+    loop {
+        let primary = depth == 0;
+        for code in &info.codes {
+            if primary && prolog_offset < info.size_of_prolog as u32 && code.offset as u32 > prolog_offset {
+                continue
+            }
+
+            match code.op {
+                nt::UnwindOp::PushNonvol(register) =>
+                    context.registers[register as usize] = try!(read_stack(&mut read, &mut context)),
+                nt::UnwindOp::AllocLarge(size) | nt::UnwindOp::AllocSmall(size) => {
+                    let rsp = context.rsp().wrapping_add(size as u64);
+                    context.set_register(nt::Register::Rsp, rsp);
+                },
+                nt::UnwindOp::SetFpreg => {
+                    let rsp = context.registers[info.frame_register as usize].wrapping_sub(info.frame_offset as u64);
+                    context.set_register(nt::Register::Rsp, rsp);
+                },
+                nt::UnwindOp::SaveNonvol { register, offset } =>
+                    context.registers[register as usize] = try!(read(frame.wrapping_add(offset as u64))),
+                nt::UnwindOp::SaveXmm128 { register, offset } => {
+                    let address = frame.wrapping_add(offset as u64);
+                    context.xmm[register as usize] = [try!(read(address)), try!(read(address.wrapping_add(8)))];
+                },
+                nt::UnwindOp::Epilog(..) => (),
+                nt::UnwindOp::PushMachframe(error_code) => {
+                    let rsp = context.rsp().wrapping_add(if error_code { 8 } else { 0 });
+                    context.rip = try!(read(rsp));
+                    context.set_register(nt::Register::Rsp, try!(read(rsp.wrapping_add(24))));
+                    machine_frame = true;
+                },
+            }
+        }
+
+        function = match info.chained {
+            Some(chained) => chained,
+            None => break,
+        };
+
+        depth += 1;
+        if depth > UNWIND_CHAIN_DEPTH {
+            return Err(parse::invalid_data("unwind chain too deep"))
+        }
+        info = try!(parse_unwind_info(view, &function));
+    }
+
+    if !machine_frame {
+        context.rip = try!(read_stack(&mut read, &mut context));
+    }
+
+    Ok(context)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::collections::HashMap;
+    use pod::Pod;
+    use testing::{ImageBuilder, put_u32};
+    use traits::PeRead;
+    use {nt, image, View};
+
+    fn image() -> Vec<u8> {
+        let mut pdata = vec![0u8; 0x24];
+        for (i, &(begin, end, unwind)) in [(0x1000, 0x1040, 0x3000), (0x1040, 0x1080, 0x3020), (0x1080, 0x1090, 0x200d)].iter().enumerate() {
+            put_u32(&mut pdata, i * 12, begin);
+            put_u32(&mut pdata, i * 12 + 4, end);
+            put_u32(&mut pdata, i * 12 + 8, unwind);
+        }
+
+        let mut xdata = vec![0u8; 0x40];
+        // push rbp; sub rsp, 0x100; lea rbp, [rsp+0x20]; mov [rsp+0x108], rbx
+        xdata[..4].copy_from_slice(&[0x01 | image::UNW_FLAG_EHANDLER << 3, 0x12, 6, 0x25]);
+        xdata[4..16].copy_from_slice(&[
+            0x12, 0x34, 0x21, 0x00,
+            0x0b, 0x03,
+            0x04, 0x01, 0x20, 0x00,
+            0x01, 0x50,
+        ]);
+        put_u32(&mut xdata, 16, 0x1080);
+        xdata[0x20..0x24].copy_from_slice(&[0x01 | image::UNW_FLAG_CHAININFO << 3, 0, 1, 0]);
+        xdata[0x24..0x26].copy_from_slice(&[0x04, 0x32]);
+        put_u32(&mut xdata, 0x28, 0x1000);
+        put_u32(&mut xdata, 0x2c, 0x1040);
+        put_u32(&mut xdata, 0x30, 0x3000);
+
+        ImageBuilder::new(nt::NtKind::Win64)
+            .directory(nt::DirectoryEntry::Exception, 0x2000, 0x24)
+            .section(0x1000, vec![0xcc; 0x90])
+            .section(0x2000, pdata)
+            .section(0x3000, xdata)
+            .build()
+    }
+
+    #[test]
+    fn lookup() {
+        let image = image();
+        let view = View::new(&image[..]).unwrap();
+
+        assert_eq!(view.runtime_functions().unwrap().count(), 3);
+        assert_eq!(view.runtime_function(0x1040).unwrap().unwrap().begin_address(), 0x1040);
+        assert_eq!(view.runtime_function(0x103f).unwrap().unwrap().begin_address(), 0x1000);
+        assert_eq!(view.runtime_function(0x1089).unwrap().unwrap().begin_address(), 0x1080);
+        assert!(view.runtime_function(0x0fff).unwrap().is_none());
+        assert!(view.runtime_function(0x1090).unwrap().is_none());
+    }
+
+    #[test]
+    fn unwind_info() {
+        let image = image();
+        let view = View::new(&image[..]).unwrap();
+        let functions = view.runtime_functions().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+
+        let info = view.unwind_info(&functions[0]).unwrap();
+        assert_eq!(info.size_of_prolog, 0x12);
+        assert_eq!(info.frame_register, 5);
+        assert_eq!(info.frame_offset, 0x20);
+        assert_eq!(info.codes, vec![
+            nt::UnwindCode { offset: 0x12, op: nt::UnwindOp::SaveNonvol { register: 3, offset: 0x108 } },
+            nt::UnwindCode { offset: 0x0b, op: nt::UnwindOp::SetFpreg },
+            nt::UnwindCode { offset: 0x04, op: nt::UnwindOp::AllocLarge(0x100) },
+            nt::UnwindCode { offset: 0x01, op: nt::UnwindOp::PushNonvol(5) },
+        ]);
+        assert_eq!(info.handler, Some(nt::UnwindHandler { address: 0x1080, data: 0x3014 }));
+        assert_eq!(info.chained, None);
+
+        let info = view.unwind_info(&functions[1]).unwrap();
+        assert_eq!(info.codes, vec![nt::UnwindCode { offset: 0x04, op: nt::UnwindOp::AllocSmall(0x20) }]);
+        assert_eq!(info.chained.map(|f| f.begin_address()), Some(0x1000));
+
+        assert_eq!(view.unwind_info(&functions[2]).unwrap(), view.unwind_info(&functions[1]).unwrap());
+        let mut bad: image::RuntimeFunction = Pod::zeroed();
+        bad.unwind_info_address.set(0x3010);
+        assert!(view.unwind_info(&bad).is_err());
+    }
+
+    fn frame_image() -> Vec<u8> {
+        let mut code = vec![0x90u8; 0x900];
+        code[..0x16].copy_from_slice(&[
+            0x40, 0x55,
+            0x48, 0x81, 0xec, 0x00, 0x01, 0x00, 0x00,
+            0x48, 0x8d, 0x6c, 0x24, 0x20,
+            0x48, 0x89, 0x9c, 0x24, 0x10, 0x01, 0x00, 0x00,
+        ]);
+        code[0x30..0x39].copy_from_slice(&[0x48, 0x8d, 0xa5, 0xe0, 0x00, 0x00, 0x00, 0x5d, 0xc3]);
+
+        let mut pdata = vec![0u8; 12];
+        put_u32(&mut pdata, 0, 0x1000);
+        put_u32(&mut pdata, 4, 0x1040);
+        put_u32(&mut pdata, 8, 0x3000);
+
+        let mut xdata = vec![0u8; 16];
+        xdata[..4].copy_from_slice(&[0x01, 0x16, 6, 0x25]);
+        xdata[4..16].copy_from_slice(&[
+            0x16, 0x34, 0x22, 0x00,
+            0x0e, 0x03,
+            0x09, 0x01, 0x20, 0x00,
+            0x02, 0x50,
+        ]);
+
+        ImageBuilder::new(nt::NtKind::Win64)
+            .directory(nt::DirectoryEntry::Exception, 0x2000, 12)
+            .section(0x1000, code)
+            .section(0x2000, pdata)
+            .section(0x3000, xdata)
+            .build()
+    }
+
+    #[test]
+    fn virtual_unwind() {
+        const BASE: u64 = 0x140000000;
+        const STACK: u64 = 0x7000;
+        const RETURN: u64 = 0x140005000;
+
+        let image = frame_image();
+        let view = View::new(&image[..]).unwrap();
+        let mut memory = HashMap::new();
+        memory.insert(STACK, RETURN);
+        memory.insert(STACK - 8, 0x1111);
+        memory.insert(STACK + 8, 0x2222);
+        let read = |address| memory.get(&address).cloned().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unmapped"));
+
+        let context = |rip: u64, rsp: u64| {
+            let mut context = nt::Context::default();
+            context.rip = BASE + rip;
+            context.set_register(nt::Register::Rsp, rsp);
+            context.set_register(nt::Register::Rbp, STACK - 0x108 + 0x20);
+            context.set_register(nt::Register::Rbx, 0xbad);
+            context
+        };
+
+        // function body, everything in the prolog has to be undone
+        let caller = view.virtual_unwind(BASE, &context(0x1020, STACK - 0x108), &read).unwrap();
+        assert_eq!((caller.rip, caller.rsp()), (RETURN, STACK + 8));
+        assert_eq!(caller.register(nt::Register::Rbp), 0x1111);
+        assert_eq!(caller.register(nt::Register::Rbx), 0x2222);
+
+        // only the push has executed
+        let caller = view.virtual_unwind(BASE, &context(0x1002, STACK - 8), &read).unwrap();
+        assert_eq!((caller.rip, caller.rsp()), (RETURN, STACK + 8));
+        assert_eq!(caller.register(nt::Register::Rbp), 0x1111);
+        assert_eq!(caller.register(nt::Register::Rbx), 0xbad);
+
+        // epilog is emulated from the instruction stream
+        let caller = view.virtual_unwind(BASE, &context(0x1030, STACK - 0x108), &read).unwrap();
+        assert_eq!((caller.rip, caller.rsp()), (RETURN, STACK + 8));
+        assert_eq!(caller.register(nt::Register::Rbp), 0x1111);
+        assert_eq!(caller.register(nt::Register::Rbx), 0xbad);
+
+        let caller = view.virtual_unwind(BASE, &context(0x1038, STACK), &read).unwrap();
+        assert_eq!((caller.rip, caller.rsp()), (RETURN, STACK + 8));
+
+        // leaf function without a RUNTIME_FUNCTION
+        let caller = view.virtual_unwind(BASE, &context(0x1800, STACK), &read).unwrap();
+        assert_eq!((caller.rip, caller.rsp()), (RETURN, STACK + 8));
+
+        assert!(view.virtual_unwind(BASE, &context(0x1800, STACK - 16), &read).is_err());
+        assert!(view.virtual_unwind(BASE, &context(0x10000, STACK), &read).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use std::io;
+use std::mem::size_of;
+use std::cmp;
+use pod::Pod;
+use {nt, image, parse};
+
+const VERSION_INFO_KEY: &'static str = "VS_VERSION_INFO";
+const STRING_FILE_INFO_KEY: &'static str = "StringFileInfo";
+const VAR_FILE_INFO_KEY: &'static str = "VarFileInfo";
+const TRANSLATION_KEY: &'static str = "Translation";
+
+const VERSION_TYPE_TEXT: u16 = 1;
+
+struct VersionNode<'a> {
+    key: String,
+    value: &'a [u8],
+    children: &'a [u8],
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    data[offset] as u16 | ((data[offset + 1] as u16) << 8)
+}
+
+fn utf16(data: &[u8]) -> Vec<u16> {
+    data.chunks(2).filter(|c| c.len() == 2).map(|c| u16_at(c, 0)).collect()
+}
+
+fn read_node(data: &[u8]) -> io::Result<(VersionNode, usize)> {
+    if data.len() < 6 {
+        return Err(parse::invalid_data("version node truncated"))
+    }
+
+    let len = u16_at(data, 0) as usize;
+    if len < 6 || len > data.len() {
+        return Err(parse::invalid_data("bad version node length"))
+    }
+
+    let node = &data[..len];
+    let value_len = u16_at(node, 2) as usize;
+    let kind = u16_at(node, 4);
+
+    let key = utf16(&node[6..]);
+    let key_len = try!(key.iter().position(|&c| c == 0)
+        .ok_or_else(|| parse::invalid_data("version node key not null terminated"))
+    );
+    let key = String::from_utf16_lossy(&key[..key_len]);
+
+    let value_start = cmp::min(align4(6 + (key_len + 1) * 2), len);
+    let value_size = if kind == VERSION_TYPE_TEXT { value_len * 2 } else { value_len };
+    let value_end = cmp::min(value_start + value_size, len);
+    let children_start = cmp::min(align4(value_end), len);
+
+    Ok((VersionNode {
+        key: key,
+        value: &node[value_start..value_end],
+        children: &node[children_start..],
+    }, cmp::min(align4(len), data.len())))
+}
+
+fn read_children<F: FnMut(VersionNode) -> io::Result<()>>(mut data: &[u8], mut f: F) -> io::Result<()> {
+    while !data.is_empty() {
+        // padding between nodes is occasionally left dangling at the end of a parent
+        if data.iter().all(|&c| c == 0) {
+            break
+        }
+
+        let (node, len) = try!(read_node(data));
+        try!(f(node));
+        data = &data[len..];
+    }
+
+    Ok(())
+}
+
+fn text(value: &[u8]) -> String {
+    let value = utf16(value);
+    let len = value.iter().position(|&c| c == 0).unwrap_or(value.len());
+    String::from_utf16_lossy(&value[..len])
+}
+
+fn string_table(node: VersionNode) -> io::Result<nt::VersionStringTable> {
+    let id = try!(u32::from_str_radix(&node.key, 16).ok()
+        .and_then(|id| if node.key.len() == 8 { Some(id) } else { None })
+        .ok_or_else(|| parse::invalid_data("bad version string table key"))
+    );
+
+    let mut strings = Vec::new();
+    try!(read_children(node.children, |node| {
+        strings.push((node.key, text(node.value)));
+        Ok(())
+    }));
+
+    Ok(nt::VersionStringTable {
+        language: (id >> 16) as u16,
+        code_page: id as u16,
+        strings: strings,
+    })
+}
+
+pub fn parse_version_info(data: &[u8]) -> io::Result<nt::VersionInfo> {
+    let (root, _) = try!(read_node(data));
+
+    if root.key != VERSION_INFO_KEY {
+        return Err(parse::invalid_data("bad version info key"))
+    }
+
+    let fixed = if root.value.len() >= size_of::<image::FixedFileInfo>() {
+        let fixed: image::FixedFileInfo = try!(Pod::merge_copy(&root.value[..size_of::<image::FixedFileInfo>()])
+            .ok_or_else(|| parse::invalid_data("bad fixed file info"))
+        );
+
+        if fixed.signature() != image::VS_FFI_SIGNATURE {
+            return Err(parse::invalid_data("bad fixed file info signature"))
+        }
+
+        Some(fixed)
+    } else {
+        None
+    };
+
+    let mut strings = Vec::new();
+    let mut translations = Vec::new();
+
+    try!(read_children(root.children, |node| {
+        if node.key == STRING_FILE_INFO_KEY {
+            read_children(node.children, |table| string_table(table).map(|table| strings.push(table)))
+        } else if node.key == VAR_FILE_INFO_KEY {
+            read_children(node.children, |var| {
+                if var.key == TRANSLATION_KEY {
+                    translations.extend(utf16(var.value).chunks(2).filter(|c| c.len() == 2).map(|c| nt::VersionTranslation {
+                        language: c[0],
+                        code_page: c[1],
+                    }));
+                }
+                Ok(())
+            })
+        } else {
+            Ok(())
+        }
+    }));
+
+    Ok(nt::VersionInfo {
+        fixed: fixed,
+        strings: strings,
+        translations: translations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pod::Pod;
+    use image;
+    use super::*;
+
+    fn node(key: &str, kind: u16, value_len: u16, value: &[u8], children: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0, 0];
+        data.extend_from_slice(&[value_len as u8, (value_len >> 8) as u8, kind as u8, (kind >> 8) as u8]);
+        for c in key.encode_utf16().chain(Some(0)) {
+            data.extend_from_slice(&[c as u8, (c >> 8) as u8]);
+        }
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+        data.extend_from_slice(value);
+        for child in children {
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            data.extend_from_slice(child);
+        }
+        let len = data.len();
+        data[0] = len as u8;
+        data[1] = (len >> 8) as u8;
+        data
+    }
+
+    fn text(value: &str) -> (u16, Vec<u8>) {
+        let value: Vec<u8> = value.encode_utf16().chain(Some(0))
+            .flat_map(|c| vec![c as u8, (c >> 8) as u8])
+            .collect();
+        ((value.len() / 2) as u16, value)
+    }
+
+    #[test]
+    fn version_info() {
+        let mut fixed: image::FixedFileInfo = Pod::zeroed();
+        fixed.signature.set(image::VS_FFI_SIGNATURE);
+        fixed.file_version_ms.set(0x00010002);
+        fixed.file_version_ls.set(0x00030004);
+        fixed.file_type.set(image::VFT_DLL);
+
+        let (company_len, company) = text("Example Corp");
+        let (version_len, version) = text("1.2.3.4");
+        let strings = node("StringFileInfo", 1, 0, &[], &[
+            node("040904b0", 1, 0, &[], &[
+                node("CompanyName", 1, company_len, &company, &[]),
+                node("FileVersion", 1, version_len, &version, &[]),
+            ]),
+        ]);
+        let vars = node("VarFileInfo", 1, 0, &[], &[
+            node("Translation", 0, 4, &[0x09, 0x04, 0xb0, 0x04], &[]),
+        ]);
+        let data = node("VS_VERSION_INFO", 0, size_of::<image::FixedFileInfo>() as u16, fixed.as_bytes(), &[strings, vars]);
+
+        let info = parse_version_info(&data).unwrap();
+        assert_eq!(info.fixed.unwrap().file_version(), (1, 2, 3, 4));
+        assert_eq!(info.fixed.unwrap().file_type(), image::VFT_DLL);
+        assert_eq!(info.string("FileVersion"), Some("1.2.3.4"));
+        assert_eq!(info.strings[0].language, 0x0409);
+        assert_eq!(info.strings[0].code_page, 0x04b0);
+        assert_eq!(info.strings[0].string("CompanyName"), Some("Example Corp"));
+        assert_eq!(info.translations, vec![nt::VersionTranslation { language: 0x0409, code_page: 0x04b0 }]);
+    }
+}
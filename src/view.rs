@@ -123,7 +123,7 @@ impl<'a, T: AsRef<[u8]> + ?Sized + 'a> PeRead for View<&'a T> {
     }
 }
 
-impl<'a, T: AsMut<[u8]> + AsRef<[u8]> + ?Sized + 'a> PeWrite<'a> for View<&'a mut T> {
+impl<'a, 'b, T: AsMut<[u8]> + AsRef<[u8]> + ?Sized + 'b> PeWrite<'a> for View<&'b mut T> {
     type Write = &'a mut [u8];
 
     fn write_section(&'a mut self, section: &image::SectionHeader, offset: u32) -> io::Result<Self::Write> {